@@ -0,0 +1,299 @@
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use manganis_cli_support::{
+    remote_asset, update_remote_asset, workspace_lockfile_path, AssetManifest, AssetManifestExt,
+    AssetType, Builder, FileSource, Lockfile, ManifestReport, LOCKFILE_ENV,
+};
+use std::path::{Path, PathBuf};
+
+/// Collect and optimize the assets of applications that use manganis
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    #[command(name = "manganis", version, about)]
+    Manganis(Cli),
+}
+
+#[derive(Args)]
+struct Cli {
+    /// Print more information about the assets being processed
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build the application and collect its assets
+    Build(BuildArgs),
+    /// Collect the assets from an application that is already built
+    Collect(CollectArgs),
+    /// List the assets an application uses
+    List {
+        /// The executable or wasm file to read the assets from
+        binary: PathBuf,
+        /// Print the assets as a table or as JSON
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+        /// The directory the assets were collected into. Used to show the size of the processed files
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Download every remote asset into the vendor cache and pin it in manganis.lock
+    ///
+    /// Without a binary, this downloads the assets that are already pinned in the lockfile so the application can be built offline.
+    Prefetch {
+        /// An executable or wasm file to read more remote assets from
+        binary: Option<PathBuf>,
+        /// Download the assets again and pin their current content
+        #[arg(long)]
+        update: bool,
+    },
+    /// Remove the collected assets
+    Clean {
+        /// The directory the assets were written to
+        #[arg(long, default_value = "./assets")]
+        out_dir: PathBuf,
+        /// Also remove the assets cached in the cargo home directory
+        #[arg(long)]
+        cache: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// The directory to write the assets to
+    #[arg(long, default_value = "./assets")]
+    out_dir: PathBuf,
+    /// Don't generate tailwind.css
+    #[arg(long)]
+    no_tailwind: bool,
+}
+
+#[derive(Args)]
+struct BuildArgs {
+    /// The package to build
+    #[arg(short, long)]
+    package: Option<String>,
+    /// The binary to build
+    #[arg(long)]
+    bin: Option<String>,
+    /// The target triple to build for
+    #[arg(long)]
+    target: Option<String>,
+    /// The location the assets are served from
+    #[arg(long)]
+    serve_location: Option<String>,
+    /// The URL the assets are served from, like a CDN
+    #[arg(long)]
+    base_url: Option<String>,
+    /// The path the application is mounted under
+    #[arg(long)]
+    path_prefix: Option<String>,
+    /// Build with the release profile
+    #[arg(short, long)]
+    release: bool,
+    /// Only read remote assets from the vendor cache
+    #[arg(long)]
+    offline: bool,
+    /// Copy the original assets under stable names instead of optimizing them, for fast local iteration
+    #[arg(long)]
+    dev: bool,
+    /// Write `.br` and `.gz` variants next to compressible assets
+    #[arg(long)]
+    precompress: bool,
+    /// The cargo profile to build with
+    #[arg(long, conflicts_with = "release")]
+    profile: Option<String>,
+    #[command(flatten)]
+    output: OutputArgs,
+    /// Extra arguments for cargo
+    #[arg(last = true)]
+    cargo_args: Vec<String>,
+}
+
+#[derive(Args)]
+struct CollectArgs {
+    /// The executable or wasm file to collect the assets from
+    binary: PathBuf,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+fn main() {
+    // The build calls this executable as the linker
+    manganis_cli_support::handle_link_intercept();
+
+    let Cargo::Manganis(cli) = Cargo::parse();
+
+    let level = if cli.verbose {
+        tracing::Level::DEBUG
+    } else {
+        tracing::Level::WARN
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .init();
+
+    if let Err(err) = run(cli.command) {
+        eprintln!("error: {err:#}");
+        std::process::exit(1);
+    }
+}
+
+fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Build(args) => build(args),
+        Command::Collect(args) => collect(args),
+        Command::List {
+            binary,
+            format,
+            out_dir,
+        } => {
+            let manifest = AssetManifest::load_from_binary(&binary)?;
+            let mut report = ManifestReport::new(&manifest);
+            if let Some(out_dir) = out_dir {
+                report = report.with_processed_sizes(out_dir);
+            }
+            match format {
+                Format::Table => print!("{}", report.to_table()),
+                Format::Json => println!("{}", report.to_json()),
+            }
+            Ok(())
+        }
+        Command::Prefetch { binary, update } => prefetch(binary.as_deref(), update),
+        Command::Clean { out_dir, cache } => clean(&out_dir, cache),
+    }
+}
+
+fn build(args: BuildArgs) -> anyhow::Result<()> {
+    let mut builder = Builder::new()
+        .release(args.release)
+        .offline(args.offline)
+        .dev(args.dev)
+        .precompress(args.precompress)
+        .out_dir(&args.output.out_dir)
+        .tailwind(!args.output.no_tailwind)
+        .cargo_args(args.cargo_args);
+    if let Some(package) = args.package {
+        builder = builder.package(package);
+    }
+    if let Some(bin) = args.bin {
+        builder = builder.bin(bin);
+    }
+    if let Some(target) = args.target {
+        builder = builder.target(target);
+    }
+    if let Some(profile) = args.profile {
+        builder = builder.profile(profile);
+    }
+    if let Some(location) = args.serve_location {
+        builder = builder.assets_serve_location(location);
+    }
+    if let Some(base_url) = args.base_url {
+        builder = builder.base_url(base_url);
+    }
+    if let Some(path_prefix) = args.path_prefix {
+        builder = builder.path_prefix(path_prefix);
+    }
+
+    let summary = builder.build()?;
+    for executable in summary.executables() {
+        eprintln!("Built {}", executable.display());
+    }
+    eprintln!(
+        "Collected {} assets into {}",
+        summary.manifest().assets().len(),
+        summary.out_dir().display()
+    );
+    Ok(())
+}
+
+fn collect(args: CollectArgs) -> anyhow::Result<()> {
+    let manifest = AssetManifest::load_from_binary(&args.binary)?;
+    let out_dir = &args.output.out_dir;
+
+    // Remove the old assets
+    let _ = std::fs::remove_dir_all(out_dir);
+    manifest.copy_static_assets_to(out_dir)?;
+
+    if !args.output.no_tailwind {
+        let css = manifest.collect_tailwind_css(true, &mut Vec::new());
+        let path = out_dir.join("tailwind.css");
+        std::fs::write(&path, css)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    eprintln!(
+        "Collected {} assets into {}",
+        manifest.assets().len(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+fn prefetch(binary: Option<&Path>, update: bool) -> anyhow::Result<()> {
+    // Pin the assets in the same lockfile `cargo manganis build` passes to the build. Nothing else runs yet, so changing the environment is safe
+    let lockfile_path = workspace_lockfile_path();
+    std::env::set_var(LOCKFILE_ENV, &lockfile_path);
+    let mut urls: Vec<_> = Lockfile::load(&lockfile_path)?
+        .assets()
+        .iter()
+        .map(|asset| asset.url().clone())
+        .collect();
+    if let Some(binary) = binary {
+        let manifest = AssetManifest::load_from_binary(binary)?;
+        for asset in manifest.assets() {
+            if let AssetType::File(file) = asset {
+                if let FileSource::Remote(url) = file.location().source() {
+                    if !urls.contains(url) {
+                        urls.push(url.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    for url in &urls {
+        let asset = if update {
+            update_remote_asset(url)?
+        } else {
+            remote_asset(url)?
+        };
+        eprintln!("{} {}", &asset.sha256()[..12], url);
+    }
+    eprintln!(
+        "Vendored {} remote assets pinned in {}",
+        urls.len(),
+        lockfile_path.display()
+    );
+    Ok(())
+}
+
+fn clean(out_dir: &Path, cache: bool) -> anyhow::Result<()> {
+    if out_dir.exists() {
+        std::fs::remove_dir_all(out_dir)
+            .with_context(|| format!("Failed to remove {}", out_dir.display()))?;
+        eprintln!("Removed {}", out_dir.display());
+    }
+
+    if cache {
+        let cache_dir = manganis_cli_support::cache::asset_cache_dir();
+        if cache_dir.exists() {
+            std::fs::remove_dir_all(&cache_dir)
+                .with_context(|| format!("Failed to remove {}", cache_dir.display()))?;
+            eprintln!("Removed {}", cache_dir.display());
+        }
+    }
+
+    Ok(())
+}
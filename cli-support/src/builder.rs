@@ -0,0 +1,411 @@
+use crate::{
+    forward_to_linker, linker_intercept, precompress_file, start_linker_intercept_with_env,
+    AssetManifestExt,
+};
+use anyhow::Context;
+use manganis_common::{
+    AssetManifest, AssetType, Config, PrecompressConfig, CONFIG_PATH_ENV, LOCKFILE_ENV,
+    LOCKFILE_NAME, OFFLINE_ENV,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The subcommand the linker intercept of a [`Builder`] calls the current executable with
+pub const LINK_SUBCOMMAND: &str = "manganis-link";
+
+/// Builds an application and collects, optimizes and copies its assets.
+///
+/// This saves the [`Config`] for the build, builds the application with the linker intercepted and then processes every asset it found. The config and the other settings are passed to cargo in its environment, so the environment of the current process is never changed and several builders can run at the same time.
+/// The executable running the builder must call [`handle_link_intercept`] at the start of `main` so it can act as the linker.
+///
+/// ```rust, no_run
+/// use manganis_cli_support::Builder;
+///
+/// fn main() {
+///     manganis_cli_support::handle_link_intercept();
+///
+///     let summary = Builder::new()
+///         .release(true)
+///         .out_dir("./dist/assets")
+///         .assets_serve_location("/assets")
+///         .build()
+///         .unwrap();
+///     println!("Collected {} assets", summary.manifest().assets().len());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Builder {
+    target: Option<String>,
+    profile: Option<String>,
+    release: bool,
+    package: Option<String>,
+    bin: Option<String>,
+    cargo_args: Vec<String>,
+    out_dir: PathBuf,
+    assets_serve_location: Option<String>,
+    base_url: Option<String>,
+    path_prefix: Option<String>,
+    tailwind: bool,
+    offline: bool,
+    dev: bool,
+    precompress: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            target: None,
+            profile: None,
+            release: false,
+            package: None,
+            bin: None,
+            cargo_args: Vec::new(),
+            out_dir: PathBuf::from("./assets"),
+            assets_serve_location: None,
+            base_url: None,
+            path_prefix: None,
+            tailwind: true,
+            offline: false,
+            dev: false,
+            precompress: false,
+        }
+    }
+}
+
+impl Builder {
+    /// Creates a new builder for the package in the current directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the target triple to build for
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Sets the cargo profile to build with
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Sets whether to build with the release profile
+    pub fn release(mut self, release: bool) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Sets the package to build in a workspace
+    pub fn package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    /// Sets the binary to build if the package has more than one
+    pub fn bin(mut self, bin: impl Into<String>) -> Self {
+        self.bin = Some(bin.into());
+        self
+    }
+
+    /// Adds extra arguments for cargo, like `--features`
+    pub fn cargo_args<I, T>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.cargo_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the directory the processed assets are written to. Defaults to `./assets`
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = out_dir.into();
+        self
+    }
+
+    /// Sets the location assets will be served from. See [`Config::with_assets_serve_location`]
+    pub fn assets_serve_location(mut self, location: impl Into<String>) -> Self {
+        self.assets_serve_location = Some(location.into());
+        self
+    }
+
+    /// Sets the URL assets are served from, like a CDN. See [`Config::with_base_url`]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sets the path the application is mounted under. See [`Config::with_path_prefix`]
+    pub fn path_prefix(mut self, path_prefix: impl Into<String>) -> Self {
+        self.path_prefix = Some(path_prefix.into());
+        self
+    }
+
+    /// Sets whether to generate `tailwind.css` in the output directory. Defaults to true
+    pub fn tailwind(mut self, tailwind: bool) -> Self {
+        self.tailwind = tailwind;
+        self
+    }
+
+    /// Sets whether remote assets may only be read from the vendor cache. See [`OFFLINE_ENV`]
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets whether assets are collected in dev mode, without processing and under stable names. See [`Config::with_dev`]
+    pub fn dev(mut self, dev: bool) -> Self {
+        self.dev = dev;
+        self
+    }
+
+    /// Sets whether `.br` and `.gz` variants are written next to compressible assets. Defaults to false. See [`PrecompressConfig`]
+    pub fn precompress(mut self, precompress: bool) -> Self {
+        self.precompress = precompress;
+        self
+    }
+
+    /// Returns the arguments passed to `cargo rustc`
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(package) = &self.package {
+            args.extend(["--package".to_string(), package.clone()]);
+        }
+        if let Some(bin) = &self.bin {
+            args.extend(["--bin".to_string(), bin.clone()]);
+        }
+        if let Some(target) = &self.target {
+            args.extend(["--target".to_string(), target.clone()]);
+        }
+        if let Some(profile) = &self.profile {
+            args.extend(["--profile".to_string(), profile.clone()]);
+        } else if self.release {
+            args.push("--release".to_string());
+        }
+        args.extend(self.cargo_args.iter().cloned());
+        args
+    }
+
+    /// Builds the application and processes its assets
+    ///
+    /// Returns an error if the build fails. The compiler output is streamed to stderr while the application builds. If the cargo args request a JSON message format, cargo's messages are forwarded to stdout instead, see [`crate::start_linker_intercept`].
+    pub fn build(&self) -> anyhow::Result<BuildSummary> {
+        let mut config = Config::default()
+            .with_dev(self.dev)
+            .with_precompress(PrecompressConfig::default().with_enabled(self.precompress));
+        if let Some(location) = &self.assets_serve_location {
+            config = config.with_assets_serve_location(location.as_str());
+        }
+        if let Some(base_url) = &self.base_url {
+            config = config.with_base_url(base_url.as_str());
+        }
+        if let Some(path_prefix) = &self.path_prefix {
+            config = config.with_path_prefix(path_prefix.as_str());
+        }
+        // Save the config for this build only, so builds with different configs can run at the same time
+        let config_path = config.save_for_build(target_dir());
+        let mut env = vec![
+            (CONFIG_PATH_ENV, config_path.into_os_string()),
+            // Tells the macro that the assets are collected
+            ("MANGANIS_SUPPORT", "true".into()),
+        ];
+        if self.offline {
+            env.push((OFFLINE_ENV, "1".into()));
+        }
+        // Pin the remote assets of every crate, including dependencies, in the lockfile of the workspace
+        env.push((LOCKFILE_ENV, workspace_lockfile_path().into_os_string()));
+
+        // The linker intercept writes the object files it found to this file
+        let objects_path = objects_file_path();
+        let build = start_linker_intercept_with_env(
+            LINK_SUBCOMMAND,
+            self.args(),
+            Some([objects_path.display()]),
+            env,
+        )
+        .context("Failed to start cargo")?;
+
+        let objects = std::fs::read(&objects_path);
+        let _ = std::fs::remove_file(&objects_path);
+
+        if !build.success() {
+            anyhow::bail!(
+                "Failed to build the application: cargo exited with {}",
+                build.status()
+            );
+        }
+
+        // If the binary was already up to date, the linker is not called
+        let mut manifest = match objects {
+            Ok(objects) => {
+                let object_files: Vec<PathBuf> = serde_json::from_slice(&objects)
+                    .context("Failed to read the object files from the linker intercept")?;
+                AssetManifest::try_load_from_objects(object_files)?
+            }
+            Err(_) => match build.executables().next() {
+                Some(executable) => AssetManifest::load_from_binary(executable)?,
+                None => AssetManifest::default(),
+            },
+        };
+
+        // Remove the old assets
+        let _ = std::fs::remove_dir_all(&self.out_dir);
+        manifest.process_static_assets_to(&self.out_dir)?;
+
+        let files = manifest
+            .assets()
+            .iter()
+            .flat_map(|asset| match asset {
+                AssetType::File(file) => vec![self.out_dir.join(file.location().unique_name())],
+                AssetType::Folder(folder) => folder
+                    .files()
+                    .iter()
+                    .map(|file| self.out_dir.join(folder.file_unique_name(file)))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+
+        let tailwind_css = if self.tailwind {
+            let css = manifest.collect_tailwind_css(true, &mut Vec::new());
+            let path = self.out_dir.join("tailwind.css");
+            std::fs::write(&path, css)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            if config.precompress().enabled() && !config.dev() {
+                let encodings = precompress_file(&path, config.precompress().min_size())?;
+                manifest.set_precompressed("tailwind.css", encodings);
+            }
+            Some(path)
+        } else {
+            None
+        };
+
+        Ok(BuildSummary {
+            manifest,
+            executables: build.executables().map(Path::to_path_buf).collect(),
+            out_dir: self.out_dir.clone(),
+            files,
+            tailwind_css,
+        })
+    }
+}
+
+/// The assets collected and processed by [`Builder::build`]
+#[derive(Debug, Clone)]
+pub struct BuildSummary {
+    manifest: AssetManifest,
+    executables: Vec<PathBuf>,
+    out_dir: PathBuf,
+    files: Vec<PathBuf>,
+    tailwind_css: Option<PathBuf>,
+}
+
+impl BuildSummary {
+    /// Returns the manifest of every asset the application uses
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// Returns the executables that were built
+    pub fn executables(&self) -> &[PathBuf] {
+        &self.executables
+    }
+
+    /// Returns the directory the assets were written to
+    pub fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    /// Returns the paths of the processed file assets
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns the path of the generated tailwind css, if it was generated
+    pub fn tailwind_css(&self) -> Option<&Path> {
+        self.tailwind_css.as_deref()
+    }
+}
+
+/// Acts as the linker if the current executable was called by the linker intercept of a [`Builder`].
+///
+/// Call this at the start of `main`. If the executable was called as the linker, this records the object files, links the application and exits the process. Otherwise it does nothing.
+pub fn handle_link_intercept() {
+    if std::env::args().nth(1).as_deref() != Some(LINK_SUBCOMMAND) {
+        return;
+    }
+
+    if let Some((link_args, object_files)) = linker_intercept(std::env::args()) {
+        if let Some(objects_path) = link_args.first() {
+            if let Err(err) =
+                std::fs::write(objects_path, serde_json::to_vec(&object_files).unwrap())
+            {
+                tracing::error!("Failed to record the object files: {}", err);
+            }
+        }
+    }
+
+    // The linker arguments come after the executable and the subcommand
+    let code = match forward_to_linker(std::env::args().skip(2)) {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(err) => {
+            tracing::error!("Failed to run the linker: {}", err);
+            1
+        }
+    };
+    std::process::exit(code);
+}
+
+fn objects_file_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "manganis-objects-{}-{:x}.json",
+        std::process::id(),
+        nanos
+    ))
+}
+
+/// Returns the lockfile remote assets are pinned in for the workspace in the current directory
+///
+/// This is the path in the [`LOCKFILE_ENV`] environment variable if it is set, and `manganis.lock` in the workspace root otherwise. [`Builder::build`] passes this path to every crate in the build.
+pub fn workspace_lockfile_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(LOCKFILE_ENV) {
+        return PathBuf::from(path);
+    }
+
+    let root = cargo_metadata()
+        .as_ref()
+        .and_then(|metadata| metadata["workspace_root"].as_str())
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    root.join(LOCKFILE_NAME)
+}
+
+/// Returns the target directory of the package in the current directory
+fn target_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    cargo_metadata()
+        .as_ref()
+        .and_then(|metadata| metadata["target_directory"].as_str())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"))
+}
+
+fn cargo_metadata() -> Option<serde_json::Value> {
+    Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice(&output.stdout).ok())
+}
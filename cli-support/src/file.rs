@@ -1,7 +1,11 @@
+use crate::precompress::precompress_file;
 use anyhow::Context;
 use image::{DynamicImage, EncodableLayout};
 use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
-use manganis_common::{CssOptions, FileAsset, FileLocation, FileOptions, ImageOptions, ImageType};
+use manganis_common::{
+    ContentEncoding, CssOptions, FileAsset, FileLocation, FileOptions, FileSource, ImageOptions,
+    ImageType,
+};
 use std::{
     io::{BufWriter, Write},
     path::{Path, PathBuf},
@@ -12,8 +16,65 @@ pub trait Process {
 }
 
 /// Process a specific file asset
+///
+/// Compressible files get `.br` and `.gz` variants next to them if precompression is enabled, see [`precompress_file`]. Assets collected in dev mode are not processed. The original file is linked or copied to the output folder instead
 pub fn process_file(file: &FileAsset, output_folder: &Path) -> anyhow::Result<()> {
-    file.options().process(file.location(), output_folder)
+    process_file_with_variants(file, output_folder).map(|_| ())
+}
+
+/// Process a specific file asset and return the precompressed variants that were written
+pub(crate) fn process_file_with_variants(
+    file: &FileAsset,
+    output_folder: &Path,
+) -> anyhow::Result<Vec<ContentEncoding>> {
+    if file.dev() {
+        link_original(file.location(), output_folder)?;
+        return Ok(Vec::new());
+    }
+    file.options().process(file.location(), output_folder)?;
+    match file.precompress_min_size() {
+        Some(min_size) => {
+            precompress_file(&output_folder.join(file.location().unique_name()), min_size)
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Symlinks the original file into the output folder so edits show up without processing it again. Falls back to copying the file
+fn link_original(input_location: &FileLocation, output_folder: &Path) -> anyhow::Result<()> {
+    let output_location = output_folder.join(input_location.unique_name());
+    let path = match input_location.source() {
+        FileSource::Local(path) => path,
+        FileSource::Remote(_) => {
+            if output_location.exists() {
+                return Ok(());
+            }
+            let bytes = input_location.read_to_bytes()?;
+            return std::fs::write(&output_location, bytes).with_context(|| {
+                format!(
+                    "Failed to write file to output location: {}",
+                    output_location.display()
+                )
+            });
+        }
+    };
+
+    if output_location.is_symlink() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if !output_location.exists() && std::os::unix::fs::symlink(path, &output_location).is_ok() {
+        return Ok(());
+    }
+    // Copies are refreshed every time, because they don't follow the original file
+    std::fs::copy(path, &output_location).with_context(|| {
+        format!(
+            "Failed to copy {} to output location: {}",
+            path.display(),
+            output_location.display()
+        )
+    })?;
+    Ok(())
 }
 
 impl Process for FileOptions {
@@ -22,23 +83,27 @@ impl Process for FileOptions {
             return Ok(());
         }
         match self {
-            Self::Other { .. } => {
-                let mut output_location = output_folder.to_path_buf();
-                output_location.push(input_location.unique_name());
-                let bytes = input_location.read_to_bytes()?;
-                std::fs::write(&output_location, bytes).with_context(|| {
-                    format!(
-                        "Failed to write file to output location: {}",
-                        output_location.display()
-                    )
-                })?;
-            }
+            Self::Other { .. } => copy_file(input_location, output_folder)?,
             Self::Css(options) => {
                 options.process(input_location, output_folder)?;
             }
             Self::Image(options) => {
                 options.process(input_location, output_folder)?;
             }
+            #[cfg(feature = "js")]
+            Self::Js(options) => {
+                options.process(input_location, output_folder)?;
+            }
+            #[cfg(not(feature = "js"))]
+            Self::Js(options) => {
+                if options.minify() || options.bundle() || options.source_map() {
+                    tracing::warn!(
+                        "Copying {} without processing it. Enable the js feature of manganis-cli-support to minify and bundle javascript",
+                        input_location.source()
+                    );
+                }
+                copy_file(input_location, output_folder)?;
+            }
             _ => todo!(),
         }
 
@@ -46,6 +111,18 @@ impl Process for FileOptions {
     }
 }
 
+/// Copies a file to the output folder as it is
+fn copy_file(input_location: &FileLocation, output_folder: &Path) -> anyhow::Result<()> {
+    let output_location = output_folder.join(input_location.unique_name());
+    let bytes = input_location.read_to_bytes()?;
+    std::fs::write(&output_location, bytes).with_context(|| {
+        format!(
+            "Failed to write file to output location: {}",
+            output_location.display()
+        )
+    })
+}
+
 impl Process for ImageOptions {
     fn process(&self, input_location: &FileLocation, output_folder: &Path) -> anyhow::Result<()> {
         let mut image =
@@ -62,15 +139,19 @@ impl Process for ImageOptions {
         match self.ty() {
             ImageType::Png => {
                 output_location.push(input_location.unique_name());
-                compress_png(image, output_location);
+                compress_png(image, output_location, self.quality());
             }
             ImageType::Jpg => {
                 output_location.push(input_location.unique_name());
-                compress_jpg(image, output_location)?;
+                compress_jpg(image, output_location, self.quality())?;
             }
             ImageType::Avif => {
                 output_location.push(input_location.unique_name());
-                if let Err(error) = image.save(&output_location) {
+                let result = match self.quality() {
+                    Some(quality) => save_avif(&image, &output_location, quality),
+                    None => image.save(&output_location).map_err(Into::into),
+                };
+                if let Err(error) = result {
                     tracing::error!("Failed to save avif image: {} with path {}. You must have the avif feature enabled to use avif assets", error, output_location.display());
                 }
             }
@@ -86,12 +167,33 @@ impl Process for ImageOptions {
     }
 }
 
-fn compress_jpg(image: DynamicImage, output_location: PathBuf) -> anyhow::Result<()> {
+fn save_avif(image: &DynamicImage, output_location: &Path, quality: u8) -> anyhow::Result<()> {
+    // The default speed of the avif encoder
+    const SPEED: u8 = 4;
+
+    let file = std::fs::File::create(output_location)?;
+    let encoder = image::codecs::avif::AvifEncoder::new_with_speed_quality(
+        BufWriter::new(file),
+        SPEED,
+        quality,
+    );
+    image.write_with_encoder(encoder)?;
+    Ok(())
+}
+
+fn compress_jpg(
+    image: DynamicImage,
+    output_location: PathBuf,
+    quality: Option<u8>,
+) -> anyhow::Result<()> {
     let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_EXT_RGBX);
     let width = image.width() as usize;
     let height = image.height() as usize;
 
     comp.set_size(width, height);
+    if let Some(quality) = quality {
+        comp.set_quality(quality as f32);
+    }
     let mut comp = comp.start_compress(Vec::new())?; // any io::Write will work
 
     comp.write_scanlines(image.to_rgba8().as_bytes())?;
@@ -104,7 +206,7 @@ fn compress_jpg(image: DynamicImage, output_location: PathBuf) -> anyhow::Result
     Ok(())
 }
 
-fn compress_png(image: DynamicImage, output_location: PathBuf) {
+fn compress_png(image: DynamicImage, output_location: PathBuf, quality: Option<u8>) {
     // Image loading/saving is outside scope of this library
     let width = image.width() as usize;
     let height = image.height() as usize;
@@ -117,7 +219,7 @@ fn compress_png(image: DynamicImage, output_location: PathBuf) {
     // Configure the library
     let mut liq = imagequant::new();
     liq.set_speed(5).unwrap();
-    liq.set_quality(0, 99).unwrap();
+    liq.set_quality(0, quality.unwrap_or(99)).unwrap();
 
     // Describe the bitmap
     let mut img = liq.new_image(&bitmap[..], width, height, 0.0).unwrap();
@@ -0,0 +1,327 @@
+use anyhow::Context;
+use manganis_common::{resolve_relative_import, FileLocation, FileSource, JsOptions, ModuleGraph};
+use std::path::{Path, PathBuf};
+use swc_atoms::Atom;
+use swc_bundler::{Bundler, Load, ModuleData, ModuleRecord};
+use swc_common::{
+    errors::{Handler, HANDLER},
+    source_map::DefaultSourceMapGenConfig,
+    sync::Lrc,
+    FileName, Globals, Mark, SourceFile, SourceMap, Span, Spanned, GLOBALS,
+};
+use swc_ecma_ast::{EsVersion, KeyValueProp, Module, Program};
+use swc_ecma_codegen::{text_writer::JsWriter, Emitter};
+use swc_ecma_loader::resolve::{Resolution, Resolve};
+use swc_ecma_minifier::option::{CompressOptions, ExtraOptions, MangleOptions, MinifyOptions};
+use swc_ecma_parser::{parse_file_as_module, parse_file_as_program, Syntax};
+use swc_ecma_transforms_base::{fixer::fixer, resolver};
+use swc_ecma_visit::VisitMutWith;
+
+use crate::file::Process;
+
+impl Process for JsOptions {
+    fn process(&self, input_location: &FileLocation, output_folder: &Path) -> anyhow::Result<()> {
+        let output_location = output_folder.join(input_location.unique_name());
+
+        let cm: Lrc<SourceMap> = Default::default();
+        let globals = Globals::new();
+        let handler = Handler::with_emitter_writer(Box::new(std::io::stderr()), Some(cm.clone()));
+        let (mut js, source_map) = GLOBALS.set(&globals, || {
+            HANDLER.set(&handler, || compile(self, input_location, &cm, &globals))
+        })?;
+
+        if let Some(source_map) = source_map {
+            let map_location = PathBuf::from(format!("{}.map", output_location.display()));
+            std::fs::write(&map_location, source_map).with_context(|| {
+                format!(
+                    "Failed to write source map to output location: {}",
+                    map_location.display()
+                )
+            })?;
+            // The map is next to the file, so its name is enough
+            let map_name = map_location
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            js.push_str(&format!("\n//# sourceMappingURL={map_name}\n"));
+        }
+
+        std::fs::write(&output_location, js).with_context(|| {
+            format!(
+                "Failed to write js to output location: {}",
+                output_location.display()
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Parses, bundles and minifies the file. Returns the code and the source map, if one was requested
+fn compile(
+    options: &JsOptions,
+    input_location: &FileLocation,
+    cm: &Lrc<SourceMap>,
+    globals: &Globals,
+) -> anyhow::Result<(String, Option<Vec<u8>>)> {
+    // Reading the file checks it against its integrity
+    let source = input_location.read_to_string()?;
+    let mut program = match input_location.source() {
+        FileSource::Local(path) if options.bundle() => Program::Module(bundle(
+            Entry {
+                path,
+                source: &source,
+            },
+            cm,
+            globals,
+        )?),
+        FileSource::Remote(url) if options.bundle() => {
+            anyhow::bail!("Failed to bundle {url}: only local javascript files can be bundled")
+        }
+        FileSource::Local(path) => {
+            let fm = cm.new_source_file(FileName::Real(path.clone()).into(), source);
+            parse_program(cm, &fm)?
+        }
+        FileSource::Remote(url) => {
+            let fm = cm.new_source_file(FileName::Custom(url.to_string()).into(), source);
+            parse_program(cm, &fm)?
+        }
+    };
+
+    if options.minify() {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+        // Top level names are kept, because scripts are often called from other scripts through them
+        program = swc_ecma_minifier::optimize(
+            program,
+            cm.clone(),
+            None,
+            None,
+            &MinifyOptions {
+                compress: Some(CompressOptions::default()),
+                mangle: Some(MangleOptions {
+                    top_level: Some(false),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            &ExtraOptions {
+                unresolved_mark,
+                top_level_mark,
+                mangle_name_cache: None,
+            },
+        );
+        program.visit_mut_with(&mut fixer(None));
+    }
+
+    let mut code = Vec::new();
+    let mut mappings = Vec::new();
+    {
+        let mut emitter = Emitter {
+            cfg: swc_ecma_codegen::Config::default().with_minify(options.minify()),
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(
+                cm.clone(),
+                "\n",
+                &mut code,
+                options.source_map().then_some(&mut mappings),
+            ),
+        };
+        emitter
+            .emit_program(&program)
+            .context("Failed to write the javascript")?;
+    }
+
+    let source_map = if options.source_map() {
+        let mut map = Vec::new();
+        cm.build_source_map(&mappings, None, DefaultSourceMapGenConfig)
+            .to_writer(&mut map)
+            .context("Failed to write the source map")?;
+        Some(map)
+    } else {
+        None
+    };
+
+    Ok((String::from_utf8(code)?, source_map))
+}
+
+fn parse_program(cm: &SourceMap, fm: &SourceFile) -> anyhow::Result<Program> {
+    parse_file_as_program(
+        fm,
+        Syntax::Es(Default::default()),
+        EsVersion::latest(),
+        None,
+        &mut Vec::new(),
+    )
+    .map_err(|err| syntax_error(cm, &err))
+}
+
+fn parse_module(cm: &SourceMap, fm: &SourceFile) -> anyhow::Result<Module> {
+    parse_file_as_module(
+        fm,
+        Syntax::Es(Default::default()),
+        EsVersion::latest(),
+        None,
+        &mut Vec::new(),
+    )
+    .map_err(|err| syntax_error(cm, &err))
+}
+
+fn syntax_error(cm: &SourceMap, err: &swc_ecma_parser::error::Error) -> anyhow::Error {
+    let location = cm.lookup_char_pos(err.span().lo);
+    anyhow::anyhow!(
+        "Failed to parse {}:{}:{}: {}",
+        location.file.name,
+        location.line,
+        location.col_display + 1,
+        err.kind().msg()
+    )
+}
+
+/// The file a bundle starts from. Its source was already read and checked against the integrity of the asset
+#[derive(Clone, Copy)]
+struct Entry<'a> {
+    path: &'a Path,
+    source: &'a str,
+}
+
+impl Entry<'_> {
+    /// Loads a file of the bundle into the source map. The entry is loaded from the source that was already read
+    fn load(&self, cm: &SourceMap, path: &Path) -> anyhow::Result<Lrc<SourceFile>> {
+        if path == self.path {
+            return Ok(cm.new_source_file(
+                FileName::Real(path.to_path_buf()).into(),
+                self.source.to_string(),
+            ));
+        }
+        cm.load_file(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Reads the source of a file of the bundle. The entry is read from the source that was already read
+    fn read(&self, path: &Path) -> anyhow::Result<String> {
+        if path == self.path {
+            return Ok(self.source.to_string());
+        }
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Bundles the entry file and every file it imports with a relative path into one ES module
+fn bundle(entry: Entry, cm: &Lrc<SourceMap>, globals: &Globals) -> anyhow::Result<Module> {
+    let external_modules = module_graph(entry)?
+        .external_imports()
+        .iter()
+        .map(|specifier| Atom::from(specifier.as_str()))
+        .collect();
+    let mut bundler = Bundler::new(
+        globals,
+        cm.clone(),
+        FileLoader {
+            cm: cm.clone(),
+            entry_path: entry.path.to_path_buf(),
+            entry_source: entry.source.to_string(),
+        },
+        RelativeResolver,
+        swc_bundler::Config {
+            require: false,
+            external_modules,
+            ..Default::default()
+        },
+        Box::new(NoImportMeta),
+    );
+
+    let entries = [("main".to_string(), FileName::Real(entry.path.to_path_buf()))]
+        .into_iter()
+        .collect();
+    let mut bundles = bundler
+        .bundle(entries)
+        .with_context(|| format!("Failed to bundle {}", entry.path.display()))?;
+    let bundle = bundles
+        .pop()
+        .with_context(|| format!("Bundling {} produced no output", entry.path.display()))?;
+    Ok(bundle.module)
+}
+
+/// Walks the module graph of the entry, or explains which import cannot be bundled
+fn module_graph(entry: Entry) -> anyhow::Result<ModuleGraph> {
+    let graph = ModuleGraph::new(entry.path, |path| entry.read(path))?;
+    if let Some((importer, specifier)) = graph.unresolved_imports().first() {
+        anyhow::bail!(
+            "Failed to resolve the import `{specifier}` in {}",
+            importer.display()
+        );
+    }
+    // The bundler leaves `import(..)` as it is, but the file it loads is not copied next to the bundle
+    if let Some((importer, specifier)) = graph.dynamic_imports().first() {
+        anyhow::bail!(
+            "Failed to bundle the dynamic import `{specifier}` in {}. Local files cannot be loaded with import() from a bundle, import them statically or collect them as their own asset",
+            importer.display()
+        );
+    }
+    Ok(graph)
+}
+
+/// Resolves a relative import, or explains which import failed
+fn resolve(importer: &Path, specifier: &str) -> anyhow::Result<PathBuf> {
+    resolve_relative_import(importer, specifier).with_context(|| {
+        format!(
+            "Failed to resolve the import `{specifier}` in {}",
+            importer.display()
+        )
+    })
+}
+
+struct FileLoader {
+    cm: Lrc<SourceMap>,
+    entry_path: PathBuf,
+    entry_source: String,
+}
+
+impl Load for FileLoader {
+    fn load(&self, file: &FileName) -> anyhow::Result<ModuleData> {
+        let FileName::Real(path) = file else {
+            anyhow::bail!("Only local javascript files can be bundled, found {file}");
+        };
+        let entry = Entry {
+            path: &self.entry_path,
+            source: &self.entry_source,
+        };
+        let fm = entry.load(&self.cm, path)?;
+        let module = parse_module(&self.cm, &fm)?;
+        Ok(ModuleData {
+            fm,
+            module,
+            helpers: Default::default(),
+        })
+    }
+}
+
+struct RelativeResolver;
+
+impl Resolve for RelativeResolver {
+    fn resolve(&self, base: &FileName, module_specifier: &str) -> anyhow::Result<Resolution> {
+        let FileName::Real(base) = base else {
+            anyhow::bail!("Failed to resolve `{module_specifier}` from {base}");
+        };
+        Ok(Resolution {
+            filename: FileName::Real(resolve(base, module_specifier)?),
+            slug: None,
+        })
+    }
+}
+
+/// `import.meta` is left as it is in the bundle
+struct NoImportMeta;
+
+impl swc_bundler::Hook for NoImportMeta {
+    fn get_import_meta_props(
+        &self,
+        _: Span,
+        _: &ModuleRecord,
+    ) -> anyhow::Result<Vec<KeyValueProp>> {
+        Ok(Vec::new())
+    }
+}
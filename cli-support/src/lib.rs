@@ -1,14 +1,32 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
+mod builder;
 #[allow(hidden_glob_reexports)]
 mod file;
+#[cfg(feature = "js")]
+mod js;
+mod linker_args;
 mod linker_intercept;
 mod manifest;
 mod marker;
+mod precompress;
+mod report;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "watch")]
+mod watch;
 
+pub use builder::*;
 pub use file::process_file;
+pub use linker_args::*;
 pub use linker_intercept::*;
 pub use manganis_common::*;
 pub use manifest::*;
 pub use marker::*;
+pub use precompress::precompress_file;
+pub use report::*;
+#[cfg(feature = "server")]
+pub use server::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
@@ -0,0 +1,355 @@
+use std::path::{Path, PathBuf};
+
+// Response files can reference other response files. Stop expanding them after this depth so a file that includes itself cannot loop forever.
+const MAX_RESPONSE_FILE_DEPTH: usize = 16;
+
+/// The quoting rules used to split a linker response file into arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFileSyntax {
+    /// The rules used by GNU ld, gold, ld64 and wasm-ld. Arguments are separated by whitespace, single and double quotes group arguments and a backslash escapes the next character
+    Gnu,
+    /// The rules used by link.exe and lld-link. Arguments are separated by whitespace and double quotes group arguments. Backslashes are only special before a double quote
+    Windows,
+}
+
+impl ResponseFileSyntax {
+    /// Returns the syntax linkers use by default on the current platform
+    ///
+    /// The host is only a guess: cross compiling to MSVC with lld-link on Linux uses the Windows rules. Prefer [`Self::for_linker`] when the linker or its arguments are known.
+    pub fn host() -> Self {
+        if cfg!(windows) {
+            Self::Windows
+        } else {
+            Self::Gnu
+        }
+    }
+
+    /// Returns the syntax of a linker, detected from its flavor
+    ///
+    /// The flavor is read from `-flavor` in the arguments, which rustc passes to `rust-lld`, or else from the name of the linker. `link.exe` and `lld-link` use the Windows rules and every other linker uses the GNU rules. Falls back to [`Self::host`] if neither is known.
+    pub fn for_linker<T: AsRef<str>>(linker: Option<&Path>, args: &[T]) -> Self {
+        let flavor = args
+            .windows(2)
+            .find(|pair| pair[0].as_ref() == "-flavor")
+            .map(|pair| pair[1].as_ref());
+        if let Some(flavor) = flavor {
+            return match flavor {
+                "link" => Self::Windows,
+                _ => Self::Gnu,
+            };
+        }
+
+        let name = linker
+            .and_then(|linker| linker.file_stem())
+            .and_then(|name| name.to_str())
+            .map(str::to_ascii_lowercase);
+        match name.as_deref() {
+            Some("link" | "lld-link") => Self::Windows,
+            // rust-lld picks its flavor from the arguments
+            Some("rust-lld") | None => Self::host(),
+            Some(_) => Self::Gnu,
+        }
+    }
+
+    /// Split the contents of a response file into arguments
+    pub fn split(&self, contents: &str) -> Vec<String> {
+        match self {
+            Self::Gnu => split_gnu(contents),
+            Self::Windows => split_windows(contents),
+        }
+    }
+}
+
+fn split_gnu(contents: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = contents.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                in_arg = true;
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            '\'' | '"' => {
+                in_arg = true;
+                let quote = c;
+                while let Some(c) = chars.next() {
+                    match c {
+                        c if c == quote => break,
+                        // Backslashes only escape characters inside of double quotes
+                        '\\' if quote == '"' => {
+                            if let Some(escaped) = chars.next() {
+                                current.push(escaped);
+                            }
+                        }
+                        c => current.push(c),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+fn split_windows(contents: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                in_arg = true;
+                let mut backslashes = 1;
+                while chars.peek() == Some(&'\\') {
+                    chars.next();
+                    backslashes += 1;
+                }
+                if chars.peek() == Some(&'"') {
+                    // 2n backslashes before a quote are n backslashes, and 2n + 1 backslashes are n backslashes and a literal quote
+                    current.extend(std::iter::repeat_n('\\', backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        chars.next();
+                        current.push('"');
+                    }
+                } else {
+                    current.extend(std::iter::repeat_n('\\', backslashes));
+                }
+            }
+            '"' => {
+                in_arg = true;
+                // Two quotes inside of a quoted argument are a literal quote
+                if in_quotes && chars.peek() == Some(&'"') {
+                    chars.next();
+                    current.push('"');
+                } else {
+                    in_quotes = !in_quotes;
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Reads a response file. Rustc may write response files as UTF-8 or UTF-16LE
+fn read_response_file(path: &Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+
+    if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return Ok(decode_utf16le(utf16));
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(contents) => Ok(contents.trim_start_matches('\u{FEFF}').to_string()),
+        Err(err) => Ok(decode_utf16le(err.as_bytes())),
+    }
+}
+
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let utf16: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|a| u16::from_le_bytes([a[0], a[1]]))
+        .collect();
+    String::from_utf16_lossy(&utf16)
+}
+
+/// Replace every `@file` argument with the arguments inside of the response file
+///
+/// Response files may be mixed with normal arguments and may reference other response files. A response file that cannot be read is left as is, like linkers treat it.
+pub fn expand_response_files<I, T>(args: I, syntax: ResponseFileSyntax) -> Vec<String>
+where
+    I: IntoIterator<Item = T>,
+    T: ToString,
+{
+    let mut expanded = Vec::new();
+    for arg in args {
+        expand_arg(arg.to_string(), syntax, 0, &mut expanded);
+    }
+    expanded
+}
+
+fn expand_arg(arg: String, syntax: ResponseFileSyntax, depth: usize, expanded: &mut Vec<String>) {
+    let contents = arg
+        .strip_prefix('@')
+        .filter(|_| depth < MAX_RESPONSE_FILE_DEPTH)
+        .and_then(|path| read_response_file(Path::new(path)).ok());
+
+    match contents {
+        Some(contents) => {
+            for arg in syntax.split(&contents) {
+                expand_arg(arg, syntax, depth + 1, expanded);
+            }
+        }
+        None => expanded.push(arg),
+    }
+}
+
+/// The inputs to a link that were found in the arguments passed to the linker
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkerInputs {
+    /// Object files and rlibs passed to the linker
+    pub object_files: Vec<PathBuf>,
+    /// Static libraries passed to the linker by path
+    pub static_libraries: Vec<PathBuf>,
+    /// Libraries passed to the linker by name with `-l`
+    pub libraries: Vec<String>,
+    /// Directories the linker searches for libraries passed with `-l`
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl LinkerInputs {
+    /// Parse the arguments passed to a linker.
+    ///
+    /// Response files are expanded with the quoting rules of the `-flavor` in the arguments, or of the current platform if there is none, and arguments forwarded with `-Wl,` or `-Xlinker` are parsed like any other argument. Use [`Self::parse_with_syntax`] with [`ResponseFileSyntax::for_linker`] if the linker is known.
+    pub fn parse<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: ToString,
+    {
+        let args: Vec<String> = args.into_iter().map(|arg| arg.to_string()).collect();
+        let syntax = ResponseFileSyntax::for_linker(None, &args);
+        Self::parse_with_syntax(args, syntax)
+    }
+
+    /// Parse the arguments passed to a linker, expanding response files with the given quoting rules.
+    pub fn parse_with_syntax<I, T>(args: I, syntax: ResponseFileSyntax) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: ToString,
+    {
+        let args = expand_response_files(args, syntax);
+
+        // Split arguments forwarded from the compiler driver to the linker
+        let mut linker_args = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(forwarded) = arg.strip_prefix("-Wl,") {
+                linker_args.extend(forwarded.split(',').map(String::from));
+            } else if arg == "-Xlinker" {
+                linker_args.extend(args.next());
+            } else {
+                linker_args.push(arg);
+            }
+        }
+
+        let mut inputs = Self::default();
+        let mut args = linker_args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(path) = strip_prefix_ignore_case(&arg, "/LIBPATH:")
+                .or_else(|| strip_prefix_ignore_case(&arg, "-LIBPATH:"))
+                .or_else(|| arg.strip_prefix("--library-path=").map(String::from))
+                .or_else(|| value_of(&arg, "-L", &mut args))
+            {
+                inputs.search_paths.push(PathBuf::from(path));
+            } else if let Some(name) = value_of(&arg, "-l", &mut args)
+                .or_else(|| arg.strip_prefix("--library=").map(String::from))
+            {
+                inputs.libraries.push(name);
+            } else if ["-o", "-T", "-z", "-m", "-e", "-arch", "-framework"].contains(&arg.as_str())
+            {
+                // Skip the value of flags that take a separate argument so it is not mistaken for an input
+                args.next();
+            } else if arg.starts_with('-') || (arg.starts_with('/') && arg.contains(':')) {
+                // Any other flag
+            } else if has_extension(&arg, &["o", "obj", "rlib"]) {
+                inputs.object_files.push(PathBuf::from(arg));
+            } else if has_extension(&arg, &["a", "lib"]) {
+                inputs.static_libraries.push(PathBuf::from(arg));
+            }
+        }
+
+        inputs
+    }
+
+    /// Find the static libraries passed with `-l` in the search paths
+    ///
+    /// Libraries that are not found or are only available as dynamic libraries are skipped.
+    pub fn resolve_static_libraries(&self) -> Vec<PathBuf> {
+        self.libraries
+            .iter()
+            .filter_map(|name| {
+                // `-l:file` links the file name exactly
+                let candidates = match name.strip_prefix(':') {
+                    Some(file) => vec![file.to_string()],
+                    None => vec![format!("lib{name}.a"), format!("{name}.lib")],
+                };
+                self.search_paths.iter().find_map(|dir| {
+                    candidates
+                        .iter()
+                        .map(|candidate| dir.join(candidate))
+                        .find(|path| path.is_file())
+                })
+            })
+            .collect()
+    }
+
+    /// Returns every file that may contain assets: object files, static libraries and static libraries found in the search paths
+    pub fn asset_files(&self) -> Vec<PathBuf> {
+        let mut files = self.object_files.clone();
+        files.extend(self.static_libraries.iter().cloned());
+        for library in self.resolve_static_libraries() {
+            if !files.contains(&library) {
+                files.push(library);
+            }
+        }
+        files
+    }
+}
+
+/// Returns the value of a flag that is either attached to the flag (`-Lpath`) or passed as the next argument (`-L path`)
+fn value_of(arg: &str, flag: &str, args: &mut impl Iterator<Item = String>) -> Option<String> {
+    match arg.strip_prefix(flag)? {
+        "" => args.next(),
+        value => Some(value.to_string()),
+    }
+}
+
+fn strip_prefix_ignore_case(arg: &str, prefix: &str) -> Option<String> {
+    let start = arg.get(..prefix.len())?;
+    start
+        .eq_ignore_ascii_case(prefix)
+        .then(|| arg[prefix.len()..].to_string())
+}
+
+fn has_extension(arg: &str, extensions: &[&str]) -> bool {
+    Path::new(arg)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
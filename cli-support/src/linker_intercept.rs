@@ -1,86 +1,40 @@
-use std::{ffi::OsStr, fs, path::PathBuf, process::Stdio};
+use crate::{LinkerInputs, ResponseFileSyntax};
+use serde::Deserialize;
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    io::{BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+};
 
-// The prefix to link args passed from parent process.
-const MG_ARG_NAME: &str = "mg-arg=";
+// The environment variable the original linker is passed to the linker intercept in.
+const LINKER_ENV: &str = "MANGANIS_LINKER";
+
+// The environment variable link args are passed from the parent process in.
+const LINK_ARGS_ENV: &str = "MANGANIS_LINK_ARGS";
 
 /// Intercept the linker for object files.
 ///
-/// Takes the arguments used in a CLI and returns a list of paths to `.rlib` or `.o` files to be searched for asset sections.
+/// Takes the arguments used in a CLI and returns a list of paths to object files, `.rlib` files and static libraries to be searched for asset sections.
+/// Response files are expanded with the quoting rules of the original linker and static libraries passed with `-l` are found in the search paths. See [`LinkerInputs`] for more information about how the arguments are parsed.
 pub fn linker_intercept<I, T>(args: I) -> Option<(Vec<String>, Vec<PathBuf>)>
 where
     I: IntoIterator<Item = T>,
     T: ToString,
 {
-    let args: Vec<String> = args.into_iter().map(|x| x.to_string()).collect();
-
-    // Check if we were provided with a command file.
-    let mut is_command_file = None;
-    for arg in args.iter() {
-        // On windows the linker args are passed in a file that is referenced by `@<file>`
-        if arg.starts_with('@') {
-            is_command_file = Some(arg.clone());
-            break;
-        }
-    }
-
-    let linker_args = match is_command_file {
-        // On unix/linux/mac the linker args are passed directly.
-        None => args,
-        // Handle windows here - uf16le and utf8 files are supported.
-        Some(arg) => {
-            let path = arg.trim().trim_start_matches('@');
-            let file_binary = fs::read(path).unwrap();
-
-            // This may be a utf-16le file. Let's try utf-8 first.
-            let content = match String::from_utf8(file_binary.clone()) {
-                Ok(s) => s,
-                Err(_) => {
-                    // Convert Vec<u8> to Vec<u16> to convert into a String
-                    let binary_u16le: Vec<u16> = file_binary
-                        .chunks_exact(2)
-                        .map(|a| u16::from_le_bytes([a[0], a[1]]))
-                        .collect();
-
-                    String::from_utf16_lossy(&binary_u16le)
-                }
-            };
-
-            // Gather linker args
-            let mut linker_args = Vec::new();
-            let lines = content.lines();
-
-            for line in lines {
-                // Remove quotes from the line - windows link args files are quoted
-                let line_parsed = line.to_string();
-                let line_parsed = line_parsed.trim_end_matches('"').to_string();
-                let line_parsed = line_parsed.trim_start_matches('"').to_string();
-
-                linker_args.push(line_parsed);
-            }
+    let args: Vec<String> = args.into_iter().map(|arg| arg.to_string()).collect();
+    let syntax = ResponseFileSyntax::for_linker(Some(&original_linker()), &args);
+    let inputs = LinkerInputs::parse_with_syntax(args, syntax);
 
-            linker_args
-        }
-    };
-
-    let mut link_args = Vec::new();
-
-    // Parse through linker args for `.o` or `.rlib` files.
-    let mut object_files: Vec<PathBuf> = Vec::new();
-    for item in linker_args {
-        // Get the working directory so it isn't lost.
-        // When rust calls the linker it doesn't pass the working dir so we need to recover it.
-        // "{MG_WORKDIR_ARG_NAME}path"
-        if item.starts_with(MG_ARG_NAME) {
-            let split: Vec<_> = item.split('=').collect();
-            link_args.push(split[1].to_string());
-            continue;
-        }
-
-        if item.ends_with(".o") || item.ends_with(".rlib") {
-            object_files.push(PathBuf::from(item));
-        }
-    }
+    // Recover the link args passed from the parent process.
+    // When rust calls the linker it doesn't pass the working dir so the parent process may pass it here.
+    let link_args = std::env::var(LINK_ARGS_ENV)
+        .ok()
+        .and_then(|args| serde_json::from_str(&args).ok())
+        .unwrap_or_default();
 
+    let object_files = inputs.asset_files();
     if object_files.is_empty() {
         return None;
     }
@@ -88,99 +42,558 @@ where
     Some((link_args, object_files))
 }
 
+/// Runs the linker rustc would have used if the linker was not intercepted.
+///
+/// Takes the arguments rustc passed to the linker intercept, without the executable and subcommand, and passes them to the original linker untouched. Exit with the returned status to report the result of linking back to rustc.
+///
+/// The original linker is detected by [`start_linker_intercept`], or by [`detect_linker`] if the intercept was started some other way.
+pub fn forward_to_linker<I, T>(linker_args: I) -> std::io::Result<ExitStatus>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<OsStr>,
+{
+    Command::new(original_linker()).args(linker_args).status()
+}
+
+// The linker rustc would have used, as detected by the process that started the intercepted build
+fn original_linker() -> PathBuf {
+    std::env::var_os(LINKER_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| detect_linker(None))
+}
+
 /// Calls cargo to build the project with a linker intercept script.
 ///
 /// The linker intercept script will call the current executable with the specified subcommand
-/// and a list of arguments provided by rustc.
+/// and a list of arguments provided by rustc. The subcommand should collect the assets with [`linker_intercept`]
+/// and then call [`forward_to_linker`] so the build produces a working binary.
+///
+/// Each call creates a new script, so multiple builds can intercept the linker at the same time. The script is deleted when the returned [`InterceptedBuild`] is dropped.
+///
+/// Cargo's progress and diagnostics are streamed to stderr while the build runs. If `args` do not set a `--message-format`, cargo's JSON messages are read to collect the diagnostics and artifacts of the build.
+/// If `args` request a JSON message format, every message is forwarded to stdout as cargo wrote it, so the caller can parse them like the output of a normal cargo build, and the diagnostics and artifacts are collected too. Any other message format is passed through untouched and no diagnostics or artifacts are collected.
+///
+/// A failed build is not an error: check [`InterceptedBuild::success`] before using the collected assets.
 pub fn start_linker_intercept<I, J>(
     subcommand: &str,
     args: I,
     link_args: Option<J>,
-) -> Result<(), std::io::Error>
+) -> Result<InterceptedBuild, std::io::Error>
+where
+    I: IntoIterator,
+    I::Item: AsRef<OsStr>,
+    J: IntoIterator,
+    J::Item: ToString,
+{
+    start_linker_intercept_with_env(
+        subcommand,
+        args,
+        link_args,
+        std::iter::empty::<(&str, &str)>(),
+    )
+}
+
+/// Like [`start_linker_intercept`], but sets extra environment variables for cargo and everything it runs, like the macro and the linker intercept.
+///
+/// The variables are only set for the build, so builds with different settings can run at the same time without changing the environment of the current process.
+pub fn start_linker_intercept_with_env<I, J, E, K, V>(
+    subcommand: &str,
+    args: I,
+    link_args: Option<J>,
+    env: E,
+) -> Result<InterceptedBuild, std::io::Error>
 where
     I: IntoIterator,
     I::Item: AsRef<OsStr>,
     J: IntoIterator,
     J::Item: ToString,
+    E: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
 {
+    let args: Vec<OsString> = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_os_string())
+        .collect();
     let exec_path = std::env::current_exe().unwrap();
 
     let mut cmd = std::process::Command::new("cargo");
+    cmd.envs(env);
     cmd.arg("rustc");
-    cmd.args(args);
+    cmd.args(&args);
+    let message_format = MessageFormat::from_args(&args);
+    if message_format == MessageFormat::Default {
+        // Ask cargo for JSON so we can find the artifacts, while still rendering diagnostics for the user
+        if std::io::stderr().is_terminal() {
+            cmd.arg("--message-format=json-diagnostic-rendered-ansi");
+        } else {
+            cmd.arg("--message-format=json");
+        }
+    }
     cmd.arg("--");
 
     // Build a temporary redirect script.
-    let script_path = create_linker_script(exec_path, subcommand).unwrap();
-    let linker_arg = format!("-Clinker={}", script_path.display());
+    let script = LinkerScript::new(&exec_path, subcommand)?;
+    let linker_arg = format!("-Clinker={}", script.path().display());
     cmd.arg(linker_arg);
 
+    // Tell the linker intercept which linker to forward to, unless the caller already chose one.
+    if std::env::var_os(LINKER_ENV).is_none() {
+        let target = target_from_args(&args);
+        cmd.env(LINKER_ENV, detect_linker(target.as_deref()));
+    }
+
     // Handle passing any arguments back to the current executable.
+    // These are passed through the environment so the arguments rustc passes to the linker stay untouched.
     if let Some(link_args) = link_args {
         let link_args: Vec<String> = link_args.into_iter().map(|x| x.to_string()).collect();
-        for link_arg in link_args {
-            let arg = format!("-Clink-arg={}{}", MG_ARG_NAME, link_arg);
-            cmd.arg(arg);
+        cmd.env(LINK_ARGS_ENV, serde_json::to_string(&link_args).unwrap());
+    }
+
+    cmd.stdin(Stdio::null()).stderr(Stdio::inherit());
+    if message_format == MessageFormat::Human {
+        let status = cmd.stdout(Stdio::inherit()).status()?;
+        return Ok(InterceptedBuild {
+            status,
+            diagnostics: Vec::new(),
+            artifacts: Vec::new(),
+            script,
+        });
+    }
+
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut diagnostics = Vec::new();
+    let mut artifacts = Vec::new();
+    for line in BufReader::new(stdout).lines() {
+        let line = line?;
+        let message = serde_json::from_str::<CargoMessage>(&line);
+        // The caller asked for cargo's messages, so pass them on untouched
+        if message_format == MessageFormat::Json || message.is_err() {
+            // Build scripts and proc macros may print to stdout too
+            println!("{line}");
         }
+        match message {
+            Ok(CargoMessage::CompilerMessage { message }) => {
+                let rendered = message.render();
+                if message_format == MessageFormat::Default {
+                    eprint!("{rendered}");
+                }
+                diagnostics.push(rendered);
+            }
+            Ok(CargoMessage::CompilerArtifact(artifact)) => artifacts.push(artifact),
+            Ok(CargoMessage::Other) | Err(_) => {}
+        }
+    }
+    let status = child.wait()?;
+
+    if !status.success() {
+        tracing::error!("The intercepted build failed with {status}");
     }
 
-    cmd.stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?
-        .wait()?;
-    Ok(())
+    Ok(InterceptedBuild {
+        status,
+        diagnostics,
+        artifacts,
+        script,
+    })
 }
 
-const LINK_SCRIPT_NAME: &str = "mg-link";
+/// The result of a build started with [`start_linker_intercept`]
+#[derive(Debug)]
+pub struct InterceptedBuild {
+    status: ExitStatus,
+    diagnostics: Vec<String>,
+    artifacts: Vec<BuildArtifact>,
+    script: LinkerScript,
+}
 
-/// Creates a temporary script that re-routes rustc linker args to a subcommand of an executable.
-fn create_linker_script(exec: PathBuf, subcommand: &str) -> Result<PathBuf, std::io::Error> {
-    #[cfg(windows)]
-    let (script, ext) = (
-        format!("echo off\n{} {} %*", exec.display(), subcommand),
-        "bat",
-    );
-    #[cfg(not(windows))]
-    let (script, ext) = (
-        format!("#!/bin/bash\n{} {} $@", exec.display(), subcommand),
-        "sh",
+impl InterceptedBuild {
+    /// Returns the exit status of cargo
+    pub fn status(&self) -> ExitStatus {
+        self.status
+    }
+
+    /// Returns true if the build succeeded
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// Returns the rendered warnings and errors the compiler emitted
+    pub fn diagnostics(&self) -> &[String] {
+        &self.diagnostics
+    }
+
+    /// Returns the artifacts cargo produced or found up to date
+    pub fn artifacts(&self) -> &[BuildArtifact] {
+        &self.artifacts
+    }
+
+    /// Returns the executables cargo produced
+    pub fn executables(&self) -> impl Iterator<Item = &Path> {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| artifact.executable.as_deref())
+    }
+
+    /// Returns the linker script used for the build. The script is deleted when the build is dropped
+    pub fn linker_script(&self) -> &LinkerScript {
+        &self.script
+    }
+}
+
+/// An artifact reported by cargo
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BuildArtifact {
+    /// The id of the package the artifact belongs to
+    pub package_id: String,
+    /// The target that was built
+    pub target: ArtifactTarget,
+    /// The files that were produced
+    pub filenames: Vec<PathBuf>,
+    /// The path to the executable if the target is a binary
+    pub executable: Option<PathBuf>,
+    /// True if the artifact was up to date and not rebuilt
+    pub fresh: bool,
+}
+
+/// The target an artifact was built for
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ArtifactTarget {
+    /// The name of the target
+    pub name: String,
+    /// The kinds of the target, like `bin` or `lib`
+    pub kind: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerMessage {
+        message: CompilerMessage,
+    },
+    CompilerArtifact(BuildArtifact),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    rendered: Option<String>,
+    #[serde(default)]
+    level: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl CompilerMessage {
+    // The rendered text is optional in cargo's messages, so fall back to a short summary without it
+    fn render(self) -> String {
+        self.rendered
+            .unwrap_or_else(|| format!("{}: {}\n", self.level, self.message))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageFormat {
+    /// No message format was passed
+    Default,
+    /// The caller asked for a JSON message format
+    Json,
+    /// The caller asked for a human readable message format
+    Human,
+}
+
+impl MessageFormat {
+    fn from_args(args: &[OsString]) -> Self {
+        let mut format = None;
+        let mut args = args.iter().filter_map(|arg| arg.to_str());
+        while let Some(arg) = args.next() {
+            if let Some(value) = arg.strip_prefix("--message-format=") {
+                format = Some(value.to_string());
+            } else if arg == "--message-format" {
+                format = args.next().map(String::from);
+            }
+        }
+        match format {
+            None => Self::Default,
+            Some(format) if format.starts_with("json") => Self::Json,
+            Some(_) => Self::Human,
+        }
+    }
+}
+
+/// Detects the linker rustc uses for a target, or the host if no target is given.
+///
+/// The linker is read from the first of these that is set:
+/// - The `CARGO_TARGET_<triple>_LINKER` environment variable
+/// - The `RUSTC_LINKER` environment variable
+/// - `-C linker` in the `RUSTFLAGS` environment variable
+/// - The `target.<triple>.linker` key in the cargo config
+/// - The default linker for the target
+pub fn detect_linker(target: Option<&str>) -> PathBuf {
+    let target = target.map(String::from).unwrap_or_else(host_target);
+
+    let target_env = format!(
+        "CARGO_TARGET_{}_LINKER",
+        target.to_uppercase().replace(['-', '.'], "_")
     );
+    if let Some(linker) = std::env::var_os(target_env) {
+        return linker.into();
+    }
+
+    if let Some(linker) = std::env::var_os("RUSTC_LINKER") {
+        return linker.into();
+    }
+
+    if let Some(linker) = linker_from_rustflags() {
+        return linker;
+    }
+
+    if let Some(linker) = linker_from_cargo_config(&target) {
+        return linker;
+    }
+
+    default_linker(&target)
+}
+
+/// Finds the value of `--target` in a list of cargo arguments.
+fn target_from_args(args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--target" {
+            return args.next().map(|target| target.to_string());
+        }
+        if let Some(target) = arg.strip_prefix("--target=") {
+            return Some(target.to_string());
+        }
+    }
+    None
+}
+
+fn rustc() -> Command {
+    Command::new(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()))
+}
+
+fn host_target() -> String {
+    rustc()
+        .arg("-vV")
+        .output()
+        .ok()
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find_map(|line| line.strip_prefix("host: ").map(String::from))
+        })
+        .unwrap_or_default()
+}
+
+fn linker_from_rustflags() -> Option<PathBuf> {
+    // Cargo passes the flags separated by the unit separator in CARGO_ENCODED_RUSTFLAGS and by whitespace in RUSTFLAGS
+    let flags: Vec<String> = match std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+        Ok(flags) if !flags.is_empty() => flags.split('\x1f').map(String::from).collect(),
+        _ => std::env::var("RUSTFLAGS")
+            .ok()?
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+    };
+
+    let mut linker = None;
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let codegen_option = match flag.strip_prefix("-C") {
+            Some("") => flags.next().map(|flag| flag.as_str()),
+            other => other,
+        };
+        // Later flags override earlier flags
+        if let Some(path) = codegen_option.and_then(|option| option.strip_prefix("linker=")) {
+            linker = Some(PathBuf::from(path));
+        }
+    }
+    linker
+}
+
+fn linker_from_cargo_config(target: &str) -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+
+    // Config files closer to the current directory take priority over the config in cargo home
+    let mut config_dirs: Vec<PathBuf> = cwd.ancestors().map(|dir| dir.join(".cargo")).collect();
+    if let Ok(cargo_home) = home::cargo_home() {
+        config_dirs.push(cargo_home);
+    }
+
+    for config_dir in config_dirs {
+        for name in ["config.toml", "config"] {
+            let path = config_dir.join(name);
+            let Ok(contents) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Ok(config) = contents.parse::<toml::Table>() else {
+                continue;
+            };
+            let linker = config
+                .get("target")
+                .and_then(|targets| targets.get(target))
+                .and_then(|target| target.get("linker"))
+                .and_then(|linker| linker.as_str());
+            if let Some(linker) = linker {
+                return Some(resolve_config_path(&config_dir, linker));
+            }
+        }
+    }
+
+    None
+}
+
+// Cargo resolves relative paths in the config relative to the directory that contains the `.cargo` directory, but looks up bare program names in the PATH
+fn resolve_config_path(config_dir: &Path, linker: &str) -> PathBuf {
+    let path = PathBuf::from(linker);
+    if path.is_relative() && path.components().count() > 1 {
+        if let Some(root) = config_dir.parent() {
+            return root.join(path);
+        }
+    }
+    path
+}
+
+fn default_linker(target: &str) -> PathBuf {
+    if target.starts_with("wasm32") || target.starts_with("wasm64") {
+        // Rust ships lld for wasm targets in the sysroot
+        let rust_lld = format!("rust-lld{}", std::env::consts::EXE_SUFFIX);
+        let sysroot = rustc()
+            .args(["--print", "sysroot"])
+            .output()
+            .ok()
+            .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+        if let Some(sysroot) = sysroot {
+            let path = sysroot
+                .join("lib")
+                .join("rustlib")
+                .join(host_target())
+                .join("bin")
+                .join(&rust_lld);
+            if path.exists() {
+                return path;
+            }
+        }
+        rust_lld.into()
+    } else if target.contains("msvc") {
+        "link.exe".into()
+    } else {
+        "cc".into()
+    }
+}
+
+const LINK_SCRIPT_NAME: &str = "mg-link";
+
+#[cfg(windows)]
+const LINK_SCRIPT_EXTENSION: &str = "bat";
+#[cfg(not(windows))]
+const LINK_SCRIPT_EXTENSION: &str = "sh";
+
+/// A temporary script that re-routes rustc linker args to a subcommand of an executable.
+///
+/// The script is deleted when this guard is dropped.
+#[derive(Debug)]
+pub struct LinkerScript {
+    path: PathBuf,
+}
+
+impl LinkerScript {
+    /// Creates a new script with a unique name that calls the executable with the subcommand and the arguments from rustc.
+    pub fn new(exec: &Path, subcommand: &str) -> Result<Self, std::io::Error> {
+        #[cfg(windows)]
+        let script = format!(
+            "@echo off\r\n{} {} %*\r\n",
+            quote_batch_arg(&exec.to_string_lossy()),
+            quote_batch_arg(subcommand)
+        );
+        #[cfg(not(windows))]
+        let script = format!(
+            "#!/bin/sh\nexec {} {} \"$@\"\n",
+            quote_shell_arg(&exec.to_string_lossy()),
+            quote_shell_arg(subcommand)
+        );
+
+        let (path, mut file) = create_unique_script()?;
+        // Remove the script if writing it fails
+        let script_guard = Self { path };
+        file.write_all(script.as_bytes())?;
+        file.sync_all()?;
+
+        Ok(script_guard)
+    }
+
+    /// Returns the path to the script
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for LinkerScript {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Creates a new file for a linker script that no other build is using.
+fn create_unique_script() -> Result<(PathBuf, fs::File), std::io::Error> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static SCRIPT_COUNT: AtomicUsize = AtomicUsize::new(0);
 
     let temp_path = std::env::temp_dir();
-    let out_name = format!("{LINK_SCRIPT_NAME}.{ext}");
-    let out = temp_path.join(out_name);
-    fs::write(&out, script)?;
+    loop {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.subsec_nanos())
+            .unwrap_or_default();
+        let count = SCRIPT_COUNT.fetch_add(1, Ordering::Relaxed);
+        let out_name = format!(
+            "{LINK_SCRIPT_NAME}-{}-{count}-{nanos:x}.{LINK_SCRIPT_EXTENSION}",
+            std::process::id()
+        );
+        let out = temp_path.join(out_name);
 
-    // Set executable permissions.
-    let mut perms = fs::metadata(&out)?.permissions();
+        // Creating the file fails if it already exists, so two builds can never share a script.
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
 
-    // We give windows RW and implied X perms.
-    // Clippy complains on any platform about this even if it's not *nix.
-    // https://rust-lang.github.io/rust-clippy/master/index.html#permissions_set_readonly_false
-    #[cfg(windows)]
-    #[allow(clippy::permissions_set_readonly_false)]
-    perms.set_readonly(false);
+        // We give nix user-RWX perms. Windows files are implicitly executable.
+        #[cfg(not(windows))]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o700);
+        }
 
-    // We give nix user-RWX perms.
-    #[cfg(not(windows))]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        perms.set_mode(0o700);
+        match options.open(&out) {
+            Ok(file) => return Ok((out, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
     }
-    fs::set_permissions(&out, perms)?;
+}
 
-    Ok(out)
+/// Quotes an argument for a POSIX shell script
+#[cfg(not(windows))]
+fn quote_shell_arg(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
 }
 
-/// Deletes the temporary script created by [`create_linker_script`].
-pub fn delete_linker_script() -> Result<(), std::io::Error> {
-    #[cfg(windows)]
-    let ext = "bat";
-    #[cfg(not(windows))]
-    let ext = "sh";
+/// Quotes an argument for a batch script. Windows paths cannot contain quotes, but percent signs must be escaped
+#[cfg(windows)]
+fn quote_batch_arg(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('%', "%%"))
+}
 
+/// Deletes the temporary script created by older versions of manganis.
+#[deprecated(
+    note = "Linker scripts are now deleted when the `LinkerScript` returned from `start_linker_intercept` is dropped"
+)]
+pub fn delete_linker_script() -> Result<(), std::io::Error> {
     let temp_path = std::env::temp_dir();
-    let file_name = format!("{LINK_SCRIPT_NAME}.{ext}");
+    let file_name = format!("{LINK_SCRIPT_NAME}.{LINK_SCRIPT_EXTENSION}");
     let file = temp_path.join(file_name);
     fs::remove_file(file)
 }
@@ -1,32 +1,27 @@
 pub use railwind::warning::Warning as TailwindWarning;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use manganis_common::{linker, AssetManifest, AssetType};
+use manganis_common::{linker, section, AssetManifest, AssetType, ContentEncoding};
 
-use crate::file::process_file;
+use crate::file::process_file_with_variants;
 
+use anyhow::Context;
 use object::{File, Object, ObjectSection};
 use std::fs;
 
-// get the text containing all the asset descriptions
+// get the data containing all the asset descriptions
 // in the "link section" of the binary
-fn get_string_manganis(file: &File) -> Option<String> {
+fn get_section_data(file: &File) -> Option<Vec<u8>> {
     for section in file.sections() {
         if let Ok(section_name) = section.name() {
             // Check if the link section matches the asset section for one of the platforms we support. This may not be the current platform if the user is cross compiling
+            // Object files may use the name passed to the `link_section` attribute while linked binaries only keep the section name
             if linker::LinkSection::ALL
                 .iter()
-                .any(|x| x.link_section == section_name)
+                .any(|x| x.link_section == section_name || x.name == section_name)
             {
-                let bytes = section.uncompressed_data().ok()?;
-                // Some platforms (e.g. macOS) start the manganis section with a null byte, we need to filter that out before we deserialize the JSON
-                return Some(
-                    std::str::from_utf8(&bytes)
-                        .ok()?
-                        .chars()
-                        .filter(|c| !c.is_control())
-                        .collect::<String>(),
-                );
+                // Padding some platforms (e.g. macOS) add to the section is skipped when the records are decoded
+                return section.uncompressed_data().ok().map(|data| data.to_vec());
             }
         }
     }
@@ -34,17 +29,30 @@ fn get_string_manganis(file: &File) -> Option<String> {
 }
 
 /// An extension trait CLI support for the asset manifest
-pub trait AssetManifestExt {
-    /// Load a manifest from a list of Manganis JSON strings.
+pub trait AssetManifestExt: Sized {
+    /// Load a manifest from the data of a list of Manganis link sections.
     ///
-    /// The asset descriptions are stored inside a manifest file that is produced when the linker is intercepted.
-    fn load(json: Vec<String>) -> Self;
+    /// Returns an error if any asset was written by a version of manganis this version cannot read.
+    fn load_from_sections(sections: Vec<Vec<u8>>) -> anyhow::Result<Self>;
     /// Load a manifest from the assets propogated through object files.
     ///
     /// The asset descriptions are stored inside a manifest file that is produced when the linker is intercepted.
+    /// Assets that cannot be read are logged and skipped, see [`AssetManifestExt::try_load_from_objects`] to handle the error instead.
     fn load_from_objects(object_paths: Vec<PathBuf>) -> Self;
+    /// Load a manifest from the assets propogated through object files.
+    ///
+    /// Returns an error if any asset was written by a version of manganis this version cannot read.
+    fn try_load_from_objects(object_paths: Vec<PathBuf>) -> anyhow::Result<Self>;
+    /// Load a manifest from the assets in a linked executable.
+    ///
+    /// The linker merges the asset sections of every dependency into the final ELF, Mach-O, PE or wasm binary, so this lets you collect assets after a plain `cargo build` without intercepting the linker.
+    fn load_from_binary(path: impl AsRef<Path>) -> anyhow::Result<Self>;
     /// Optimize and copy all assets in the manifest to a folder
     fn copy_static_assets_to(&self, location: impl Into<PathBuf>) -> anyhow::Result<()>;
+    /// Optimize and copy all assets in the manifest to a folder, like [`AssetManifestExt::copy_static_assets_to`]
+    ///
+    /// The precompressed variants written next to the processed files are recorded in the manifest, see [`AssetManifest::precompressed`].
+    fn process_static_assets_to(&mut self, location: impl Into<PathBuf>) -> anyhow::Result<()>;
     /// Collect all tailwind classes and generate string with the output css
     fn collect_tailwind_css(
         &self,
@@ -54,52 +62,69 @@ pub trait AssetManifestExt {
 }
 
 impl AssetManifestExt for AssetManifest {
-    fn load(json: Vec<String>) -> Self {
-        let mut all_assets = Vec::new();
+    fn load_from_sections(sections: Vec<Vec<u8>>) -> anyhow::Result<Self> {
+        let mut all_records = Vec::new();
+        let mut errors = Vec::new();
 
-        // Collect all assets for each manganis string found.
-        for item in json {
-            let mut assets = deserialize_assets(item.as_str());
-            all_assets.append(&mut assets);
+        // Collect all assets for each manganis section found.
+        for data in sections {
+            let decoded = section::decode_section(&data);
+            all_records.extend(decoded.records);
+            for error in decoded.errors {
+                if !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            let errors = errors
+                .iter()
+                .map(|e| format!("  - {e}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            anyhow::bail!("Failed to read the assets from the application:\n{errors}");
         }
 
         // If we don't see any manganis assets used in the binary, just return an empty manifest
-        if all_assets.is_empty() {
-            return Self::default();
+        if all_records.is_empty() {
+            return Ok(Self::default());
         };
 
-        Self::new(all_assets)
+        Ok(Self::from_records(all_records))
     }
 
     fn load_from_objects(object_files: Vec<PathBuf>) -> Self {
-        let json = get_json_from_object_files(object_files);
-        Self::load(json)
+        let mut all_records = Vec::new();
+        for data in get_sections_from_object_files(object_files) {
+            let decoded = section::decode_section(&data);
+            for error in decoded.errors {
+                tracing::error!("Failed to read an asset from the application: {}", error);
+            }
+            all_records.extend(decoded.records);
+        }
+        Self::from_records(all_records)
+    }
+
+    fn try_load_from_objects(object_files: Vec<PathBuf>) -> anyhow::Result<Self> {
+        let sections = get_sections_from_object_files(object_files);
+        Self::load_from_sections(sections)
+    }
+
+    fn load_from_binary(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let sections = get_sections_from_binary(path)?;
+        Self::load_from_sections(sections)
     }
 
     fn copy_static_assets_to(&self, location: impl Into<PathBuf>) -> anyhow::Result<()> {
-        let location = location.into();
-        match std::fs::create_dir_all(&location) {
-            Ok(_) => {}
-            Err(err) => {
-                tracing::error!("Failed to create directory for static assets: {}", err);
-                return Err(err.into());
-            }
-        }
+        copy_static_assets(self, location.into()).map(|_| ())
+    }
 
-        self.assets().iter().try_for_each(|asset| {
-            if let AssetType::File(file_asset) = asset {
-                tracing::info!("Optimizing and bundling {}", file_asset);
-                tracing::trace!("Copying asset from {:?} to {:?}", file_asset, location);
-                match process_file(file_asset, &location) {
-                    Ok(_) => {}
-                    Err(err) => {
-                        tracing::error!("Failed to copy static asset: {}", err);
-                        return Err(err);
-                    }
-                }
-            }
-            Ok::<(), anyhow::Error>(())
-        })
+    fn process_static_assets_to(&mut self, location: impl Into<PathBuf>) -> anyhow::Result<()> {
+        for (unique_name, encodings) in copy_static_assets(self, location.into())? {
+            self.set_precompressed(unique_name, encodings);
+        }
+        Ok(())
     }
 
     fn collect_tailwind_css(
@@ -124,76 +149,211 @@ impl AssetManifestExt for AssetManifest {
     }
 }
 
-fn deserialize_assets(json: &str) -> Vec<AssetType> {
-    let deserializer = serde_json::Deserializer::from_str(json);
-    deserializer
-        .into_iter::<AssetType>()
-        .map(|x| x.unwrap())
-        .collect()
+/// Optimize and copy all assets in the manifest to a folder and return the precompressed variants written for each unique name
+fn copy_static_assets(
+    manifest: &AssetManifest,
+    location: PathBuf,
+) -> anyhow::Result<Vec<(String, Vec<ContentEncoding>)>> {
+    match std::fs::create_dir_all(&location) {
+        Ok(_) => {}
+        Err(err) => {
+            tracing::error!("Failed to create directory for static assets: {}", err);
+            return Err(err.into());
+        }
+    }
+
+    let mut precompressed = Vec::new();
+    for asset in manifest.assets() {
+        match asset {
+            AssetType::File(file_asset) => {
+                tracing::info!("Optimizing and bundling {}", file_asset);
+                tracing::trace!("Copying asset from {:?} to {:?}", file_asset, location);
+                match process_file_with_variants(file_asset, &location) {
+                    Ok(encodings) => precompressed
+                        .push((file_asset.location().unique_name().to_string(), encodings)),
+                    Err(err) => {
+                        tracing::error!("Failed to copy static asset: {}", err);
+                        return Err(err);
+                    }
+                }
+            }
+            AssetType::Folder(folder) => {
+                tracing::info!(
+                    "Optimizing and bundling {} files from {}",
+                    folder.files().len(),
+                    folder.source().display()
+                );
+                let folder_location = location.join(folder.unique_name());
+                for file in folder.files() {
+                    let output = folder_location.join(file.asset().location().unique_name());
+                    if let Some(parent) = output.parent() {
+                        std::fs::create_dir_all(parent).with_context(|| {
+                            format!("Failed to create directory {}", parent.display())
+                        })?;
+                    }
+                    match process_file_with_variants(file.asset(), &folder_location) {
+                        Ok(encodings) => {
+                            precompressed.push((folder.file_unique_name(file), encodings))
+                        }
+                        Err(err) => {
+                            tracing::error!("Failed to copy static asset: {}", err);
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(precompressed)
+}
+
+/// Extract the data of the Manganis link section from a linked executable.
+pub fn get_sections_from_binary(path: impl AsRef<Path>) -> anyhow::Result<Vec<Vec<u8>>> {
+    let path = path.as_ref();
+    let binary_data = fs::read(path)
+        .with_context(|| format!("Failed to read binary from location: {}", path.display()))?;
+    let file = object::File::parse(&*binary_data)
+        .with_context(|| format!("Failed to parse binary: {}", path.display()))?;
+
+    Ok(get_section_data(&file).into_iter().collect())
 }
 
-/// Extract JSON Manganis strings from a list of object files.
-pub fn get_json_from_object_files(object_paths: Vec<PathBuf>) -> Vec<String> {
-    let mut all_json = Vec::new();
+/// Extract the data of the Manganis link sections from a list of object files.
+///
+/// Object files, `.rlib` files and static archives (including GNU thin archives) are searched regardless of their extension. Archive members that cannot be parsed are skipped. Objects that only contain LLVM bitcode, like the objects produced with `-C linker-plugin-lto`, are searched for asset records directly.
+///
+/// If you build with LTO and some assets are still missing, you can collect them from the final executable with [`AssetManifestExt::load_from_binary`] instead.
+pub fn get_sections_from_object_files(object_paths: Vec<PathBuf>) -> Vec<Vec<u8>> {
+    let mut all_sections = Vec::new();
 
     for path in object_paths {
-        let Some(ext) = path.extension() else {
-            continue;
+        // Read binary data and try getting assets from the manganis section
+        let binary_data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::warn!("Failed to read object file {}: {}", path.display(), err);
+                continue;
+            }
         };
 
-        let Some(ext) = ext.to_str() else {
-            continue;
-        };
+        // rlibs and static libraries are archives with object files inside.
+        if is_archive(&binary_data) {
+            collect_archive_sections(&path, &binary_data, &mut all_sections);
+        } else {
+            let location = path.display().to_string();
+            collect_object_sections(&location, &binary_data, &mut all_sections);
+        }
+    }
+
+    all_sections
+}
+
+/// Archive members that never contain object files
+const SKIPPED_ARCHIVE_MEMBERS: &[&str] = &[
+    "/",
+    "//",
+    "/SYM64/",
+    "__.SYMDEF",
+    "__.SYMDEF SORTED",
+    "lib.rmeta",
+];
 
-        let is_rlib = match ext {
-            "rlib" => true,
-            "o" => false,
-            _ => continue,
+fn is_archive(data: &[u8]) -> bool {
+    data.starts_with(b"!<arch>\n")
+        || data.starts_with(b"!<thin>\n")
+        || data.starts_with(b"<bigaf>\n")
+}
+
+fn collect_archive_sections(path: &Path, data: &[u8], sections: &mut Vec<Vec<u8>>) {
+    let archive = match object::read::archive::ArchiveFile::parse(data) {
+        Ok(archive) => archive,
+        Err(err) => {
+            tracing::warn!("Failed to parse archive {}: {}", path.display(), err);
+            return;
+        }
+    };
+
+    // Archives can contain many object files so we look through each member and collect each manganis section here.
+    for member in archive.members() {
+        let member = match member {
+            Ok(member) => member,
+            Err(err) => {
+                tracing::warn!("Failed to read archive {}: {}", path.display(), err);
+                break;
+            }
         };
+        let name = String::from_utf8_lossy(member.name()).to_string();
+        if SKIPPED_ARCHIVE_MEMBERS.contains(&name.as_str()) {
+            continue;
+        }
+        let location = format!("{}({})", path.display(), name);
 
-        // Read binary data and try getting assets from manganis string
-        let binary_data = fs::read(path).unwrap();
-
-        // rlibs are archives with object files inside.
-        let mut data = match is_rlib {
-            false => {
-                // Parse an unarchived object file. We use a Vec to match the return types.
-                let file = object::File::parse(&*binary_data).unwrap();
-                let mut data = Vec::new();
-                if let Some(string) = get_string_manganis(&file) {
-                    data.push(string);
-                }
-                data
+        if member.is_thin() {
+            // Thin archives only store the path to the member relative to the archive
+            let member_path = path.parent().unwrap_or(Path::new("")).join(&name);
+            match fs::read(&member_path) {
+                Ok(member_data) => collect_object_sections(&location, &member_data, sections),
+                Err(err) => tracing::warn!(
+                    "Failed to read thin archive member {}: {}",
+                    member_path.display(),
+                    err
+                ),
             }
-            true => {
-                let file = object::read::archive::ArchiveFile::parse(&*binary_data).unwrap();
-
-                // rlibs can contain many object files so we collect each manganis string here.
-                let mut manganis_strings = Vec::new();
-
-                // Look through each archive member for object files.
-                // Read the archive member's binary data (we know it's an object file)
-                // And parse it with the normal `object::File::parse` to find the manganis string.
-                for member in file.members() {
-                    let member = member.unwrap();
-                    let name = String::from_utf8_lossy(member.name()).to_string();
-
-                    // Check if the archive member is an object file and parse it.
-                    if name.ends_with(".o") {
-                        let data = member.data(&*binary_data).unwrap();
-                        let o_file = object::File::parse(data).unwrap();
-                        if let Some(manganis_str) = get_string_manganis(&o_file) {
-                            manganis_strings.push(manganis_str);
-                        }
-                    }
-                }
+        } else {
+            match member.data(data) {
+                Ok(member_data) => collect_object_sections(&location, member_data, sections),
+                Err(err) => tracing::warn!("Failed to read archive member {}: {}", location, err),
+            }
+        }
+    }
+}
 
-                manganis_strings
+fn collect_object_sections(location: &str, data: &[u8], sections: &mut Vec<Vec<u8>>) {
+    match object::File::parse(data) {
+        Ok(file) => {
+            if let Some(section) = get_section_data(&file) {
+                sections.push(section);
             }
+        }
+        Err(_) if is_bitcode(data) => {
+            tracing::debug!(
+                "{location} only contains LLVM bitcode, searching it for assets directly"
+            );
+            let section = get_section_data_from_bitcode(data);
+            if !section.is_empty() {
+                sections.push(section);
+            }
+        }
+        Err(err) => {
+            tracing::debug!("Skipping {location} because it is not an object file: {err}");
+        }
+    }
+}
+
+fn is_bitcode(data: &[u8]) -> bool {
+    // Raw bitcode or bitcode inside of a wrapper header
+    data.starts_with(b"BC\xC0\xDE") || data.starts_with(&[0xDE, 0xC0, 0x17, 0x0B])
+}
+
+// LLVM stores the constant the macro emits as an array of 8 bit fields, but bitcode is a stream of bits so the array does not need to start on a byte boundary.
+// We search the data at every bit offset and join the records we find into a single section.
+fn get_section_data_from_bitcode(data: &[u8]) -> Vec<u8> {
+    let mut records = Vec::new();
+
+    for shift in 0..8 {
+        let shifted: Vec<u8> = if shift == 0 {
+            data.to_vec()
+        } else {
+            data.windows(2)
+                .map(|bytes| (bytes[0] >> shift) | (bytes[1] << (8 - shift)))
+                .collect()
         };
 
-        all_json.append(&mut data);
+        for record in section::find_records(&shifted) {
+            records.extend_from_slice(record);
+        }
     }
 
-    all_json
+    records
 }
@@ -0,0 +1,85 @@
+use anyhow::Context;
+use flate2::{write::GzEncoder, Compression};
+use manganis_common::ContentEncoding;
+use std::{io::Write, path::Path};
+
+/// Writes a `.br` and a `.gz` variant next to a processed file if it is at least `min_size` bytes. Returns the variants that exist afterwards
+///
+/// Variants that are not smaller than the file are not written. Existing variants are only kept if they are newer than the file, because some outputs like `tailwind.css` or the files of dev assets are written again under the same name. Variants that no longer apply are removed.
+pub fn precompress_file(path: &Path, min_size: u64) -> anyhow::Result<Vec<ContentEncoding>> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if metadata.len() < min_size {
+        for encoding in ContentEncoding::ALL {
+            remove_variant(&variant_path(path, encoding))?;
+        }
+        return Ok(Vec::new());
+    }
+
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut written = Vec::new();
+    for encoding in ContentEncoding::ALL {
+        let variant = variant_path(path, encoding);
+        if is_up_to_date(&variant, &metadata) {
+            written.push(encoding);
+            continue;
+        }
+
+        let compressed = compress(&bytes, encoding)?;
+        if compressed.len() >= bytes.len() {
+            tracing::debug!(
+                "Skipping the {} variant of {} because it is not smaller",
+                encoding.name(),
+                path.display()
+            );
+            remove_variant(&variant)?;
+            continue;
+        }
+        std::fs::write(&variant, compressed)
+            .with_context(|| format!("Failed to write {}", variant.display()))?;
+        written.push(encoding);
+    }
+    Ok(written)
+}
+
+/// Returns whether a variant exists and was written after the file it was compressed from
+fn is_up_to_date(variant: &Path, file: &std::fs::Metadata) -> bool {
+    let modified = |metadata: &std::fs::Metadata| metadata.modified().ok();
+    std::fs::metadata(variant).is_ok_and(|variant| {
+        matches!((modified(&variant), modified(file)), (Some(variant), Some(file)) if variant >= file)
+    })
+}
+
+fn remove_variant(variant: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(variant) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("Failed to remove {}", variant.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the path of the variant of a file in an encoding
+pub(crate) fn variant_path(path: &Path, encoding: ContentEncoding) -> std::path::PathBuf {
+    let mut variant = path.as_os_str().to_os_string();
+    variant.push(".");
+    variant.push(encoding.extension());
+    variant.into()
+}
+
+fn compress(bytes: &[u8], encoding: ContentEncoding) -> anyhow::Result<Vec<u8>> {
+    match encoding {
+        ContentEncoding::Brotli => {
+            // The highest quality is slow, but the files are only compressed once
+            let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+            writer.write_all(bytes)?;
+            Ok(writer.into_inner())
+        }
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(bytes)?;
+            Ok(encoder.finish()?)
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use crate::precompress::variant_path;
+use manganis_common::{
+    AssetManifest, AssetType, ContentEncoding, FileAsset, FileOptions, FileSource,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::Path};
+
+/// The version of the JSON schema of [`ManifestReport`]. This is bumped whenever a field is removed or changes meaning
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// A description of every asset in a manifest that can be written as JSON or as a table.
+///
+/// The JSON schema is stable and versioned with [`REPORT_SCHEMA_VERSION`] so other tools can read it:
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "assets": [
+///     {
+///       "kind": "file",
+///       "package": "my-app",
+///       "source": "/path/to/my-app/assets/logo.png",
+///       "unique_name": "logo123456789.avif",
+///       "served_path": "/assets/logo123456789.avif",
+///       "file_type": "image",
+///       "extension": "avif",
+///       "options": "avif (compressed)",
+///       "url_encoded": false,
+///       "embedded": false,
+///       "processed_size": 8192,
+///       "precompressed": []
+///     },
+///     { "kind": "tailwind", "package": "my-app", "classes": "flex p-4" },
+///     { "kind": "metadata", "package": "my-app", "key": "title", "value": "My App" }
+///   ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ManifestReport {
+    /// The version of the schema
+    pub schema_version: u32,
+    /// The assets in the manifest
+    pub assets: Vec<AssetReport>,
+}
+
+/// A single asset in a [`ManifestReport`]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetReport {
+    /// A file asset
+    File {
+        /// The crate that declared the asset, if it is known
+        package: Option<String>,
+        /// The path or URL the asset is read from
+        source: String,
+        /// The path of the processed file relative to the output directory
+        unique_name: String,
+        /// The path the asset is served from. This is `None` if the asset is url encoded
+        served_path: Option<String>,
+        /// The type of the file: `image`, `video`, `font`, `css`, `js` or `other`
+        file_type: String,
+        /// The extension of the processed file
+        extension: Option<String>,
+        /// A summary of the options used to process the file
+        options: String,
+        /// Whether the asset is embedded in the application as a data URL
+        url_encoded: bool,
+        /// Whether the processed file is embedded in the application
+        #[serde(default)]
+        embedded: bool,
+        /// The size in bytes of the processed file, if it has been processed
+        processed_size: Option<u64>,
+        /// The precompressed variants written next to the processed file, like `["brotli", "gzip"]`
+        #[serde(default)]
+        precompressed: Vec<ContentEncoding>,
+    },
+    /// A set of tailwind classes
+    Tailwind {
+        /// The crate that declared the asset, if it is known
+        package: Option<String>,
+        /// The classes that are used
+        classes: String,
+    },
+    /// A metadata key and value
+    Metadata {
+        /// The crate that declared the asset, if it is known
+        package: Option<String>,
+        /// The metadata key
+        key: String,
+        /// The metadata value
+        value: String,
+    },
+}
+
+impl ManifestReport {
+    /// Describe every asset in the manifest. Each file in a folder asset is described as a file
+    pub fn new(manifest: &AssetManifest) -> Self {
+        let assets = manifest
+            .assets_with_packages()
+            .flat_map(|(asset, package)| {
+                let package = package.map(String::from);
+                match asset {
+                    AssetType::File(file) => vec![AssetReport::file(
+                        package,
+                        file,
+                        file.location().unique_name().to_string(),
+                        (!file.url_encoded()).then(|| file.served_location()),
+                    )],
+                    AssetType::Folder(folder) => folder
+                        .files()
+                        .iter()
+                        .map(|file| {
+                            AssetReport::file(
+                                package.clone(),
+                                file.asset(),
+                                folder.file_unique_name(file),
+                                Some(folder.served_file_location(file)),
+                            )
+                        })
+                        .collect(),
+                    AssetType::Tailwind(tailwind) => vec![AssetReport::Tailwind {
+                        package,
+                        classes: tailwind.classes().to_string(),
+                    }],
+                    AssetType::Metadata(metadata) => vec![AssetReport::Metadata {
+                        package,
+                        key: metadata.key().to_string(),
+                        value: metadata.value().to_string(),
+                    }],
+                }
+            })
+            .collect();
+
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            assets,
+        }
+    }
+
+    /// Fill in the size and the precompressed variants of each file asset that has been processed into the given directory
+    pub fn with_processed_sizes(mut self, out_dir: impl AsRef<Path>) -> Self {
+        let out_dir = out_dir.as_ref();
+        for asset in &mut self.assets {
+            if let AssetReport::File {
+                unique_name,
+                processed_size,
+                precompressed,
+                ..
+            } = asset
+            {
+                let path = out_dir.join(&*unique_name);
+                *processed_size = std::fs::metadata(&path).ok().map(|metadata| metadata.len());
+                *precompressed = ContentEncoding::ALL
+                    .into_iter()
+                    .filter(|encoding| variant_path(&path, *encoding).is_file())
+                    .collect();
+            }
+        }
+        self
+    }
+
+    /// Serialize the report as pretty printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Render the report as a plain text table
+    pub fn to_table(&self) -> String {
+        let header = ["KIND", "PACKAGE", "SOURCE", "OUTPUT", "OPTIONS", "SIZE"].map(String::from);
+        let mut rows = vec![header];
+        for asset in &self.assets {
+            rows.push(asset.table_row());
+        }
+
+        let mut widths = [0; 6];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+}
+
+impl Display for ManifestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_table())
+    }
+}
+
+impl AssetReport {
+    fn file(
+        package: Option<String>,
+        file: &FileAsset,
+        unique_name: String,
+        served_path: Option<String>,
+    ) -> Self {
+        let file_type = match file.options() {
+            FileOptions::Image(_) => "image",
+            FileOptions::Video(_) => "video",
+            FileOptions::Font(_) => "font",
+            FileOptions::Css(_) => "css",
+            FileOptions::Js(_) => "js",
+            FileOptions::Other(_) => "other",
+        };
+        AssetReport::File {
+            package,
+            // The display of a source is shortened for logs, the report keeps the whole path
+            source: match file.location().source() {
+                FileSource::Local(path) => path.display().to_string(),
+                FileSource::Remote(url) => url.to_string(),
+            },
+            unique_name,
+            served_path,
+            file_type: file_type.to_string(),
+            extension: file.options().extension().map(String::from),
+            options: file.options().to_string(),
+            url_encoded: file.url_encoded(),
+            embedded: file.embedded(),
+            processed_size: None,
+            precompressed: Vec::new(),
+        }
+    }
+
+    fn table_row(&self) -> [String; 6] {
+        let package = |package: &Option<String>| package.clone().unwrap_or_else(|| "-".into());
+        match self {
+            AssetReport::File {
+                package: crate_name,
+                source,
+                served_path,
+                options,
+                processed_size,
+                precompressed,
+                ..
+            } => {
+                let mut size = processed_size
+                    .map(format_size)
+                    .unwrap_or_else(|| "-".into());
+                if !precompressed.is_empty() {
+                    let encodings: Vec<_> = precompressed
+                        .iter()
+                        .map(|encoding| encoding.extension())
+                        .collect();
+                    size.push_str(&format!(" ({})", encodings.join(", ")));
+                }
+                [
+                    "file".into(),
+                    package(crate_name),
+                    source.clone(),
+                    served_path
+                        .clone()
+                        .unwrap_or_else(|| "(url encoded)".into()),
+                    options.clone(),
+                    size,
+                ]
+            }
+            AssetReport::Tailwind {
+                package: crate_name,
+                classes,
+            } => [
+                "tailwind".into(),
+                package(crate_name),
+                classes.clone(),
+                "tailwind.css".into(),
+                String::new(),
+                "-".into(),
+            ],
+            AssetReport::Metadata {
+                package: crate_name,
+                key,
+                value,
+            } => [
+                "metadata".into(),
+                package(crate_name),
+                format!("{key} = {value}"),
+                String::new(),
+                String::new(),
+                "-".into(),
+            ],
+        }
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1048575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::Context;
+use manganis_common::{get_mime_from_ext, AssetManifest, AssetType, ContentEncoding};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+/// A small HTTP server for the assets in the output directory of [`crate::AssetManifestExt::copy_static_assets_to`]
+///
+/// It is meant for local testing and examples, not for production. The server:
+/// - Sets the content type from the extension of the file
+/// - Marks the files with hashed names from the manifest as immutable, so browsers cache them forever
+/// - Serves the `.br` or `.gz` variant of a file if the manifest recorded it and the client accepts the encoding
+/// - Sends ETags and answers `If-None-Match` with `304 Not Modified`
+///
+/// ```rust, no_run
+/// use manganis_cli_support::{AssetManifest, AssetManifestExt, AssetServer};
+///
+/// let mut manifest = AssetManifest::load_from_binary("target/debug/my-app").unwrap();
+/// manifest.process_static_assets_to("./assets").unwrap();
+///
+/// AssetServer::new("./assets")
+///     .serve_location("/assets")
+///     .manifest(&manifest)
+///     .bind("127.0.0.1:8080")
+///     .unwrap()
+///     .run()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetServer {
+    root: PathBuf,
+    serve_location: String,
+    immutable: HashSet<String>,
+    precompressed: HashMap<String, Vec<ContentEncoding>>,
+}
+
+impl AssetServer {
+    /// Creates a server for the files in a directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            serve_location: "/".to_string(),
+            immutable: HashSet::new(),
+            precompressed: HashMap::new(),
+        }
+    }
+
+    /// Sets the URL path the directory is served under, like `/assets`. Defaults to `/`
+    pub fn serve_location(mut self, location: impl Into<String>) -> Self {
+        self.serve_location = location.into();
+        self
+    }
+
+    /// Marks the processed files of the manifest as immutable. Their names change whenever their content does, so they are served with a long-lived `Cache-Control` header
+    ///
+    /// Assets collected in dev mode keep their name when they change, so they are not marked. The precompressed variants recorded in the manifest are served to clients that accept them. Variants that are not in the manifest are never served, so a leftover variant of an older file cannot shadow it.
+    pub fn manifest(mut self, manifest: &AssetManifest) -> Self {
+        self.precompressed.extend(
+            manifest
+                .precompressed_files()
+                .map(|(name, encodings)| (name.to_string(), encodings.to_vec())),
+        );
+        for asset in manifest.assets() {
+            match asset {
+                AssetType::File(file) if !file.dev() && !file.url_encoded() => {
+                    self.immutable
+                        .insert(file.location().unique_name().to_string());
+                }
+                AssetType::Folder(folder) if !folder.dev() => {
+                    self.immutable.extend(
+                        folder
+                            .files()
+                            .iter()
+                            .map(|file| folder.file_unique_name(file)),
+                    );
+                }
+                _ => {}
+            }
+        }
+        self
+    }
+
+    /// Starts listening on an address. Use port 0 to pick a free port
+    pub fn bind(self, addr: impl ToSocketAddrs) -> anyhow::Result<BoundAssetServer> {
+        let listener = TcpListener::bind(addr).context("Failed to bind the asset server")?;
+        Ok(BoundAssetServer {
+            listener,
+            server: Arc::new(self),
+        })
+    }
+
+    fn handle(&self, stream: TcpStream) -> anyhow::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or_default(), parts.next());
+        let head = method == "HEAD";
+        let mut response = Response {
+            stream,
+            head,
+            headers: Vec::new(),
+        };
+        if method != "GET" && !head {
+            response.header("Allow", "GET, HEAD");
+            return response.send(405, b"Method Not Allowed");
+        }
+        let Some(relative_path) = target.and_then(|target| self.relative_path(target)) else {
+            return response.send(404, b"Not Found");
+        };
+        let path = self.root.join(&relative_path);
+        if !path.is_file() {
+            return response.send(404, b"Not Found");
+        }
+
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        response.header("Content-Type", get_mime_from_ext(extension));
+
+        // Serve the first variant the client accepts, brotli before gzip
+        let accepted = header("accept-encoding").unwrap_or_default();
+        let mut served = path.clone();
+        let variants = self
+            .precompressed
+            .get(&relative_path)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for encoding in ContentEncoding::ALL {
+            if !variants.contains(&encoding) {
+                continue;
+            }
+            let variant = self.root.join(encoding.variant_name(&relative_path));
+            if served == path && accepts(accepted, encoding.name()) && variant.is_file() {
+                response.header("Content-Encoding", encoding.name());
+                served = variant;
+            }
+        }
+        if !variants.is_empty() {
+            response.header("Vary", "Accept-Encoding");
+        }
+
+        let cache_control = if self.immutable.contains(&relative_path) {
+            "public, max-age=31536000, immutable"
+        } else {
+            "no-cache"
+        };
+        response.header("Cache-Control", cache_control);
+
+        let etag = etag(&served)?;
+        response.header("ETag", &etag);
+        let not_modified = header("if-none-match").is_some_and(|tags| {
+            tags.split(',')
+                .any(|tag| tag.trim() == etag || tag.trim() == "*")
+        });
+        if not_modified {
+            return response.send(304, b"");
+        }
+
+        let body = std::fs::read(&served)
+            .with_context(|| format!("Failed to read {}", served.display()))?;
+        response.send(200, &body)
+    }
+
+    /// Returns the path of the requested file relative to the root, with `/` separators
+    fn relative_path(&self, target: &str) -> Option<String> {
+        let path = target.split(['?', '#']).next()?;
+        let location = self.serve_location.trim_matches('/');
+        let path = path.trim_start_matches('/');
+        let path = if location.is_empty() {
+            path
+        } else {
+            path.strip_prefix(location)?.strip_prefix('/')?
+        };
+
+        let mut segments = Vec::new();
+        for segment in path.split('/') {
+            let segment = percent_decode(segment)?;
+            // Never serve files outside of the root
+            if segment == ".." || segment.contains(['/', '\\']) {
+                return None;
+            }
+            if !segment.is_empty() && segment != "." {
+                segments.push(segment);
+            }
+        }
+        (!segments.is_empty()).then(|| segments.join("/"))
+    }
+}
+
+/// An [`AssetServer`] that is listening for connections
+#[derive(Debug)]
+pub struct BoundAssetServer {
+    listener: TcpListener,
+    server: Arc<AssetServer>,
+}
+
+impl BoundAssetServer {
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves requests until the process exits. Each connection is handled on its own thread
+    pub fn run(self) -> anyhow::Result<()> {
+        tracing::info!(
+            "Serving {} on http://{}",
+            self.server.root.display(),
+            self.local_addr()?
+        );
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::warn!("Failed to accept a connection: {}", err);
+                    continue;
+                }
+            };
+            let server = self.server.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = server.handle(stream) {
+                    tracing::warn!("Failed to serve an asset: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Serves requests on a background thread
+    pub fn spawn(self) -> std::thread::JoinHandle<anyhow::Result<()>> {
+        std::thread::spawn(move || self.run())
+    }
+}
+
+struct Response {
+    stream: TcpStream,
+    head: bool,
+    headers: Vec<(&'static str, String)>,
+}
+
+impl Response {
+    fn header(&mut self, name: &'static str, value: &str) {
+        self.headers.push((name, value.to_string()));
+    }
+
+    fn send(mut self, status: u16, body: &[u8]) -> anyhow::Result<()> {
+        let reason = reason_phrase(status);
+        let mut response = format!("HTTP/1.1 {status} {reason}\r\n");
+        for (name, value) in &self.headers {
+            response.push_str(&format!("{name}: {value}\r\n"));
+        }
+        response.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        ));
+        self.stream.write_all(response.as_bytes())?;
+        if !self.head {
+            self.stream.write_all(body)?;
+        }
+        Ok(self.stream.flush()?)
+    }
+}
+
+/// Returns the reason phrase of a status code the server sends
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        // The reason phrase is optional in HTTP/1.1
+        _ => "",
+    }
+}
+
+/// Returns whether an `Accept-Encoding` header accepts an encoding
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|accepted| {
+        let mut parts = accepted.split(';');
+        let name = parts.next().unwrap_or_default().trim();
+        let refused = parts.any(|param| matches!(param.trim(), "q=0" | "q=0.0" | "q=0.00"));
+        (name == encoding || name == "*") && !refused
+    })
+}
+
+/// Creates an ETag from the size and modification time of a file
+fn etag(path: &Path) -> anyhow::Result<String> {
+    let metadata =
+        std::fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+    Ok(format!("\"{:x}-{:x}\"", metadata.len(), modified))
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,260 @@
+use crate::file::process_file_with_variants;
+use anyhow::Context;
+use manganis_common::{AssetManifest, AssetType, ContentEncoding, FileAsset, FileSource};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+/// How long to wait for more events after a file changed. Editors often write a file in several steps
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// An asset that was processed again because its source changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetChange {
+    /// The source file that changed
+    pub source: PathBuf,
+    /// The path of the old processed file relative to the output directory
+    pub old_unique_name: String,
+    /// The path of the new processed file relative to the output directory
+    pub new_unique_name: String,
+}
+
+/// Watches the local files of a manifest and processes the assets again when their source changes.
+///
+/// Each change gets a new unique name, like the macro would generate after a rebuild. A dev server can use the [`AssetChange`]s to serve the new file under the old name or to hot reload the page.
+///
+/// ```rust, no_run
+/// use manganis_cli_support::{AssetManifest, AssetManifestExt, AssetWatcher};
+///
+/// let mut manifest = AssetManifest::load_from_binary("target/debug/my-app").unwrap();
+/// manifest.process_static_assets_to("./assets").unwrap();
+///
+/// let mut watcher = AssetWatcher::new(manifest, "./assets").unwrap();
+/// loop {
+///     for change in watcher.wait().unwrap() {
+///         println!("{} -> {}", change.old_unique_name, change.new_unique_name);
+///     }
+/// }
+/// ```
+pub struct AssetWatcher {
+    manifest: AssetManifest,
+    out_dir: PathBuf,
+    sources: HashSet<PathBuf>,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl AssetWatcher {
+    /// Starts watching every local file in the manifest. Changed assets are processed into the output directory
+    pub fn new(manifest: AssetManifest, out_dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let sources: HashSet<PathBuf> = manifest
+            .assets()
+            .iter()
+            .flat_map(|asset| match asset {
+                AssetType::File(file) => local_source(file).into_iter().collect(),
+                AssetType::Folder(folder) => folder
+                    .files()
+                    .iter()
+                    .filter_map(|file| local_source(file.asset()))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .map(Path::to_path_buf)
+            .collect();
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to create the file watcher")?;
+        // Watch the directories instead of the files, because many editors replace a file when they save it
+        let dirs: HashSet<&Path> = sources.iter().filter_map(|path| path.parent()).collect();
+        for dir in dirs {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+        }
+        tracing::debug!("Watching {} asset sources", sources.len());
+
+        Ok(Self {
+            manifest,
+            out_dir: out_dir.into(),
+            sources,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Returns the manifest with the unique names of every change so far
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// Returns the directory the assets are processed into
+    pub fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    /// Blocks until a watched file changes, processes the assets that changed and returns the changes
+    pub fn wait(&mut self) -> anyhow::Result<Vec<AssetChange>> {
+        loop {
+            let event = self.events.recv().context("The file watcher stopped")?;
+            let changed = self.changed_sources(event);
+            if !changed.is_empty() {
+                return self.process_changes(&changed);
+            }
+        }
+    }
+
+    /// Like [`AssetWatcher::wait`], but returns no changes if no watched file changed before the timeout
+    pub fn wait_timeout(&mut self, timeout: Duration) -> anyhow::Result<Vec<AssetChange>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let event = match self.events.recv_timeout(remaining) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => return Ok(Vec::new()),
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("The file watcher stopped"),
+            };
+            let changed = self.changed_sources(event);
+            if !changed.is_empty() {
+                return self.process_changes(&changed);
+            }
+        }
+    }
+
+    /// Collects the watched sources that changed in the event and the events right after it
+    fn changed_sources(&self, first: notify::Result<notify::Event>) -> HashSet<PathBuf> {
+        let mut changed = HashSet::new();
+        let mut event = Some(first);
+        while let Some(next) = event {
+            match next {
+                Ok(next) if matches!(next.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    changed.extend(
+                        next.paths
+                            .into_iter()
+                            .filter(|path| self.sources.contains(path)),
+                    );
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("Failed to watch the assets: {}", err),
+            }
+            event = self.events.recv_timeout(DEBOUNCE).ok();
+        }
+        changed
+    }
+
+    fn process_changes(&mut self, changed: &HashSet<PathBuf>) -> anyhow::Result<Vec<AssetChange>> {
+        let mut changes = Vec::new();
+        let old_variants: HashMap<String, Vec<ContentEncoding>> = self
+            .manifest
+            .precompressed_files()
+            .map(|(name, encodings)| (name.to_string(), encodings.to_vec()))
+            .collect();
+        // The variants of each file that moved, recorded in the manifest once every asset is processed
+        let mut variants = Vec::new();
+        for asset in self.manifest.assets_mut() {
+            match asset {
+                AssetType::File(file) => {
+                    let Some(source) = local_source(file).filter(|path| changed.contains(*path))
+                    else {
+                        continue;
+                    };
+                    let source = source.to_path_buf();
+                    let old_unique_name = file.location().unique_name().to_string();
+                    file.regenerate_unique_name();
+                    tracing::info!("Processing {} again", source.display());
+                    let encodings = process_file_with_variants(file, &self.out_dir)?;
+                    variants.push((
+                        old_unique_name.clone(),
+                        file.location().unique_name().to_string(),
+                        encodings,
+                    ));
+                    changes.push(AssetChange {
+                        source,
+                        old_unique_name,
+                        new_unique_name: file.location().unique_name().to_string(),
+                    });
+                }
+                AssetType::Folder(folder) => {
+                    let is_changed = |file: &FileAsset| {
+                        local_source(file).is_some_and(|path| changed.contains(path))
+                    };
+                    if !folder.files().iter().any(|file| is_changed(file.asset())) {
+                        continue;
+                    }
+
+                    // The folder gets a new name, so every file in it moves unless it was collected in dev mode
+                    let old_folder = folder.clone();
+                    folder.regenerate_unique_name();
+                    let folder_location = self.out_dir.join(folder.unique_name());
+                    for (old_file, file) in old_folder.files().iter().zip(folder.files()) {
+                        let old_unique_name = old_folder.file_unique_name(old_file);
+                        let new_unique_name = folder.file_unique_name(file);
+                        let output = self.out_dir.join(&new_unique_name);
+                        if let Some(parent) = output.parent() {
+                            std::fs::create_dir_all(parent).with_context(|| {
+                                format!("Failed to create directory {}", parent.display())
+                            })?;
+                        }
+
+                        // Only process the files that changed. The others are copied from the old folder. Dev folders keep their name, so unchanged files are already in place
+                        let copied = !is_changed(file.asset())
+                            && (old_unique_name == new_unique_name
+                                || std::fs::copy(self.out_dir.join(&old_unique_name), &output)
+                                    .is_ok());
+                        let encodings = if copied {
+                            let encodings = old_variants
+                                .get(&old_unique_name)
+                                .cloned()
+                                .unwrap_or_default();
+                            for encoding in encodings
+                                .iter()
+                                .filter(|_| old_unique_name != new_unique_name)
+                            {
+                                std::fs::copy(
+                                    self.out_dir.join(encoding.variant_name(&old_unique_name)),
+                                    self.out_dir.join(encoding.variant_name(&new_unique_name)),
+                                )
+                                .context("Failed to copy a precompressed variant")?;
+                            }
+                            encodings
+                        } else {
+                            process_file_with_variants(file.asset(), &folder_location)?
+                        };
+                        variants.push((
+                            old_unique_name.clone(),
+                            new_unique_name.clone(),
+                            encodings,
+                        ));
+
+                        changes.push(AssetChange {
+                            source: local_source(file.asset())
+                                .map(Path::to_path_buf)
+                                .unwrap_or_default(),
+                            old_unique_name,
+                            new_unique_name,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (old_unique_name, _, _) in &variants {
+            self.manifest.set_precompressed(old_unique_name, Vec::new());
+        }
+        for (_, new_unique_name, encodings) in variants {
+            self.manifest.set_precompressed(new_unique_name, encodings);
+        }
+        Ok(changes)
+    }
+}
+
+fn local_source(file: &FileAsset) -> Option<&Path> {
+    match file.location().source() {
+        FileSource::Local(path) => Some(path),
+        FileSource::Remote(_) => None,
+    }
+}
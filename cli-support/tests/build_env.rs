@@ -0,0 +1,58 @@
+mod common;
+
+use common::{manifest, TestDir};
+use manganis_cli_support::start_linker_intercept_with_env;
+
+// Creates a library crate whose build script records the environment it was built with
+fn package(name: &str) -> TestDir {
+    TestDir::with_files(
+        name,
+        &[
+            ("Cargo.toml", manifest(name)),
+            ("src/lib.rs", String::new()),
+            (
+                "build.rs",
+                r#"fn main() {
+    let config = std::env::var("MANGANIS_CONFIG").unwrap_or_default();
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    std::fs::write(std::path::Path::new(&dir).join("config.txt"), config).unwrap();
+}
+"#
+                .to_string(),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn parallel_builds_see_their_own_environment() {
+    let builds: Vec<_> = ["first", "second"]
+        .into_iter()
+        .map(|name| {
+            std::thread::spawn(move || {
+                let dir = package(name);
+                let manifest_path = dir.join("Cargo.toml");
+                let build = start_linker_intercept_with_env(
+                    "link",
+                    ["--manifest-path".as_ref(), manifest_path.as_os_str()],
+                    None::<Vec<String>>,
+                    [("MANGANIS_CONFIG", format!("{name}.toml"))],
+                )
+                .unwrap();
+                assert!(build.success(), "{:#?}", build.diagnostics());
+                (name, dir)
+            })
+        })
+        .collect();
+
+    for build in builds {
+        let (name, dir) = build.join().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("config.txt")).unwrap(),
+            format!("{name}.toml")
+        );
+    }
+
+    // The environment of the current process is left alone
+    assert!(std::env::var_os("MANGANIS_CONFIG").is_none());
+}
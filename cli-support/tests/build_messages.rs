@@ -0,0 +1,82 @@
+mod common;
+
+use common::{manifest, TestDir};
+use manganis_cli_support::start_linker_intercept;
+use std::process::Command;
+
+// Set when the test binary runs a build in a child process, so the output of the build can be captured
+const BUILD_ENV: &str = "MANGANIS_TEST_BUILD";
+
+// Creates a library crate that compiles with a warning
+fn package(name: &str) -> TestDir {
+    TestDir::with_files(
+        name,
+        &[
+            ("Cargo.toml", manifest(name)),
+            ("src/lib.rs", "fn never_called() {}\n".to_string()),
+        ],
+    )
+}
+
+// Runs the build in a child process and returns its stdout and stderr
+fn run_build(name: &str, message_format: Option<&str>) -> (String, String) {
+    let dir = package(name);
+    let mut args = vec![
+        "--manifest-path".to_string(),
+        dir.join("Cargo.toml").display().to_string(),
+    ];
+    args.extend(message_format.map(|format| format!("--message-format={format}")));
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["build_in_child", "--exact", "--nocapture"])
+        .env(BUILD_ENV, serde_json::to_string(&args).unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn build_in_child() {
+    let Ok(args) = std::env::var(BUILD_ENV) else {
+        return;
+    };
+    let args: Vec<String> = serde_json::from_str(&args).unwrap();
+    let build = start_linker_intercept("link", args, None::<Vec<String>>).unwrap();
+    assert!(build.success());
+    assert_eq!(build.diagnostics().len(), 1, "{:#?}", build.diagnostics());
+    assert!(build.diagnostics()[0].contains("never_called"));
+}
+
+#[test]
+fn renders_diagnostics_by_default() {
+    let (stdout, stderr) = run_build("rendered", None);
+    assert!(stderr.contains("never_called"), "{stderr}");
+    assert!(!stdout.contains("compiler-message"), "{stdout}");
+}
+
+#[test]
+fn forwards_json_messages() {
+    let (stdout, stderr) = run_build("json", Some("json"));
+    // The test harness prints the name of the test before the first message on the same line
+    let messages: Vec<serde_json::Value> = stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str(&line[line.find('{')?..]).ok())
+        .collect();
+    let warning = messages
+        .iter()
+        .find(|message| message["reason"] == "compiler-message")
+        .expect("the warning is forwarded");
+    assert!(warning["message"]["rendered"]
+        .as_str()
+        .unwrap()
+        .contains("never_called"));
+    assert!(messages
+        .iter()
+        .any(|message| message["reason"] == "compiler-artifact"));
+    // The caller renders the messages it asked for
+    assert!(!stderr.contains("never_called"), "{stderr}");
+}
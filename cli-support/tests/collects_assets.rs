@@ -1,7 +1,7 @@
-use manganis_cli_support::{AssetManifestExt, ManganisSupportGuard};
+use manganis_cli_support::{AssetManifestExt, ManganisSupportGuard, ManifestReport};
 use manganis_common::{AssetManifest, AssetType, Config};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::Command;
 
 #[test]
 fn collects_assets() {
@@ -45,22 +45,16 @@ fn build() {
 
     // Then build your application
     let args = ["--target", "wasm32-unknown-unknown", "--release"];
-    Command::new("cargo")
-        .arg("build")
-        .args(args)
-        .current_dir(&test_package_dir)
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap()
-        .wait()
-        .unwrap();
 
     println!("Collecting Assets");
 
     // Call the helper function to intercept the Rust linker.
     // We will pass the current working directory as it may get lost.
     let link_args = vec![format!("{}", test_package_dir.display())];
-    manganis_cli_support::start_linker_intercept("link", args, Some(link_args)).unwrap();
+    let build =
+        manganis_cli_support::start_linker_intercept("link", args, Some(link_args)).unwrap();
+    assert!(build.success(), "{:#?}", build.diagnostics());
+    assert!(build.executables().next().is_some());
 }
 
 fn link() {
@@ -74,7 +68,7 @@ fn link() {
     let assets = AssetManifest::load_from_objects(object_files);
 
     let all_assets = assets.assets();
-    println!("{:#?}", all_assets);
+    println!("{}", ManifestReport::new(&assets));
 
     let locations = all_assets
         .iter()
@@ -91,6 +85,10 @@ fn link() {
     let assets_dir = PathBuf::from("./assets");
     assets.copy_static_assets_to(assets_dir).unwrap();
 
+    // Link the wasm binary with the linker rustc would have used
+    let status = manganis_cli_support::forward_to_linker(std::env::args().skip(2)).unwrap();
+    assert!(status.success());
+
     // Then run the application
     let status = Command::new("cargo")
         .arg("run")
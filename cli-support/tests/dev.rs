@@ -0,0 +1,82 @@
+mod common;
+
+use common::{use_config, TestDir};
+use manganis_cli_support::{
+    process_file, Config, CssOptions, FileAsset, FileOptions, FileSource, PrecompressConfig,
+};
+use std::path::PathBuf;
+
+// Every test in this file collects assets in dev mode
+fn dev_mode() {
+    use_config(
+        Config::default()
+            .with_dev(true)
+            .with_precompress(PrecompressConfig::default().with_enabled(true)),
+    );
+}
+
+#[test]
+fn names_only_depend_on_the_path() {
+    dev_mode();
+    let dir = TestDir::new("names");
+    let path = dir.join("style.css");
+    std::fs::write(&path, "body { color: red; }").unwrap();
+
+    let asset = FileAsset::new(FileSource::Local(path.clone()));
+    assert!(asset.dev());
+    let name = asset.location().unique_name().to_string();
+    assert!(name.starts_with("stylecss"), "{name}");
+    assert!(name.ends_with(".css"), "{name}");
+
+    // Editing the file or changing its options keeps the name
+    std::fs::write(&path, "body { color: blue; }").unwrap();
+    let mut edited = FileAsset::new(FileSource::Local(path.clone()));
+    edited.with_options_mut(|options| *options = FileOptions::Css(CssOptions::new(false)));
+    assert_eq!(edited.location().unique_name(), name);
+
+    // Files with the same name in different directories do not collide
+    std::fs::create_dir_all(dir.join("other")).unwrap();
+    std::fs::write(dir.join("other/style.css"), "").unwrap();
+    let other = FileAsset::new(FileSource::Local(dir.join("other/style.css")));
+    assert_ne!(other.location().unique_name(), name);
+}
+
+#[test]
+fn keeps_the_extension_of_the_source() {
+    dev_mode();
+    let dir = TestDir::new("extension");
+    let path = dir.join("image.png");
+    std::fs::write(&path, []).unwrap();
+    let asset = FileAsset::new(FileSource::Local(path));
+    assert!(matches!(asset.options(), FileOptions::Image(_)));
+    assert!(asset.location().unique_name().ends_with(".png"));
+}
+
+#[test]
+fn serves_the_original_file() {
+    dev_mode();
+    let dir = TestDir::new("link");
+    let path = dir.join("style.css");
+    std::fs::write(&path, "body {\n    color: red;\n}\n".repeat(100)).unwrap();
+    let asset = FileAsset::new(FileSource::Local(path.clone()));
+    assert_eq!(asset.precompress_min_size(), None);
+
+    let out = dir.join("out");
+    std::fs::create_dir_all(&out).unwrap();
+    process_file(&asset, &out).unwrap();
+    let output = out.join(asset.location().unique_name());
+    // The file is not minified and gets no compressed variants
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        std::fs::read_to_string(&path).unwrap()
+    );
+    assert!(!PathBuf::from(format!("{}.br", output.display())).exists());
+
+    // Edits show up after processing the file again
+    std::fs::write(&path, "body { color: blue; }").unwrap();
+    process_file(&asset, &out).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "body { color: blue; }"
+    );
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "js")]
+
+mod common;
+
+use common::TestDir;
+use manganis_cli_support::{process_file, FileAsset, FileOptions, FileSource, JsOptions};
+use std::path::PathBuf;
+
+#[test]
+fn bundles_relative_imports_and_keeps_external_ones() {
+    let dir = TestDir::new("bundle");
+    std::fs::write(
+        dir.join("greet.js"),
+        "export function greet(name) { return 'Hello, ' + name; }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("main.js"),
+        "import { greet } from './greet';\nimport { h } from 'https://esm.sh/preact';\nexport const message = greet('world');\nexport const node = h('p', null, message);\n",
+    )
+    .unwrap();
+
+    let mut options = JsOptions::new(true);
+    options.set_bundle(true);
+    options.set_source_map(true);
+    let asset = FileAsset::new(FileSource::Local(
+        dir.join("main.js").canonicalize().unwrap(),
+    ))
+    .with_options(FileOptions::Js(options));
+    let out = dir.join("out");
+    std::fs::create_dir_all(&out).unwrap();
+    process_file(&asset, &out).unwrap();
+
+    let output = out.join(asset.location().unique_name());
+    let js = std::fs::read_to_string(&output).unwrap();
+    assert!(js.contains("Hello, "));
+    assert!(js.contains("https://esm.sh/preact"));
+    assert!(!js.contains("./greet"));
+    assert!(js.contains("//# sourceMappingURL="));
+    assert!(PathBuf::from(format!("{}.map", output.display())).is_file());
+}
+
+fn process(dir: &std::path::Path, name: &str, options: JsOptions) -> anyhow::Result<String> {
+    let asset = FileAsset::new(FileSource::Local(dir.join(name).canonicalize().unwrap()))
+        .with_options(FileOptions::Js(options));
+    let out = dir.join("out");
+    std::fs::create_dir_all(&out).unwrap();
+    process_file(&asset, &out)?;
+    Ok(std::fs::read_to_string(out.join(asset.location().unique_name())).unwrap())
+}
+
+#[test]
+fn minifies_scripts_and_modules() {
+    let dir = TestDir::new("script");
+    // A script keeps its top level names, so other scripts can still call them
+    std::fs::write(
+        dir.join("script.js"),
+        "function greetEveryone(names) {\n    for (const name of names) {\n        console.log('Hello, ' + name);\n    }\n}\n",
+    )
+    .unwrap();
+    let js = process(&dir, "script.js", JsOptions::new(true)).unwrap();
+    assert!(js.contains("greetEveryone"));
+    assert!(!js.contains("names"));
+    assert!(!js.contains('\n'));
+
+    std::fs::write(
+        dir.join("module.mjs"),
+        "import { h } from 'preact';\nexport function render(message) {\n    return h('p', null, message);\n}\n",
+    )
+    .unwrap();
+    let js = process(&dir, "module.mjs", JsOptions::new(true)).unwrap();
+    assert!(js.contains("from\"preact\""), "{js}");
+    assert!(js.contains("export"));
+    assert!(!js.contains("message"));
+}
+
+#[test]
+fn keeps_the_code_readable_without_minify() {
+    let dir = TestDir::new("no-minify");
+    std::fs::write(
+        dir.join("script.js"),
+        "function greet(name) {\n    // Say hello\n    return 'Hello, ' + name;\n}\n",
+    )
+    .unwrap();
+    let js = process(&dir, "script.js", JsOptions::new(false)).unwrap();
+    assert!(js.contains("function greet(name)"));
+    assert!(js.contains('\n'));
+    assert!(!js.contains("sourceMappingURL"));
+}
+
+#[test]
+fn reports_syntax_errors_with_their_location() {
+    let dir = TestDir::new("syntax-error");
+    std::fs::write(dir.join("broken.js"), "const a = 1;\nconst = 2;\n").unwrap();
+    let err = process(&dir, "broken.js", JsOptions::new(true)).unwrap_err();
+    let message = format!("{err:#}");
+    assert!(message.contains("broken.js:2:"), "{message}");
+
+    // Errors in an imported file name that file
+    std::fs::write(
+        dir.join("main.js"),
+        "import { a } from './broken.js';\nconsole.log(a);\n",
+    )
+    .unwrap();
+    let mut options = JsOptions::new(true);
+    options.set_bundle(true);
+    let err = process(&dir, "main.js", options).unwrap_err();
+    let message = format!("{err:#}");
+    assert!(message.contains("broken.js:2:"), "{message}");
+}
+
+#[test]
+fn rejects_dynamic_imports_of_local_files() {
+    let dir = TestDir::with_files(
+        "dynamic",
+        &[
+            (
+                "main.js",
+                "const { lazy } = await import('./lazy.js');\nconsole.log(lazy);\n",
+            ),
+            ("lazy.js", "export const lazy = 1;\n"),
+        ],
+    );
+    let mut options = JsOptions::new(true);
+    options.set_bundle(true);
+    let err = process(&dir, "main.js", options).unwrap_err();
+    let message = format!("{err:#}");
+    assert!(message.contains("dynamic import `./lazy.js`"), "{message}");
+    assert!(message.contains("main.js"), "{message}");
+}
@@ -0,0 +1,338 @@
+mod common;
+
+use common::TestDir;
+use manganis_cli_support::{expand_response_files, LinkerInputs, ResponseFileSyntax};
+use std::path::{Path, PathBuf};
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn parses_linux_cc_command_line() {
+    // Captured from `cargo build` on x86_64-unknown-linux-gnu
+    let args = [
+        "-m64",
+        "/tmp/rustcXXCyI5/symbols.o",
+        "/app/target/debug/deps/app-1f6b2c3a.app.3c1e2d-cgu.0.rcgu.o",
+        "/app/target/debug/deps/app-1f6b2c3a.4x2hjq8rqv3tqt3n.rcgu.o",
+        "-Wl,--as-needed",
+        "-Wl,-Bstatic",
+        "/app/target/debug/deps/libmanganis-8a1b2c3d.rlib",
+        "/home/user/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/x86_64-unknown-linux-gnu/lib/libstd-9c6a2f1e.rlib",
+        "-Wl,-Bdynamic",
+        "-lgcc_s",
+        "-lc",
+        "-Wl,--eh-frame-hdr",
+        "-Wl,-z,noexecstack",
+        "-L",
+        "/home/user/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/x86_64-unknown-linux-gnu/lib",
+        "-o",
+        "/app/target/debug/deps/app-1f6b2c3a",
+        "-Wl,--gc-sections",
+        "-pie",
+        "-Wl,-z,relro,-z,now",
+        "-nodefaultlibs",
+    ];
+
+    let inputs = LinkerInputs::parse(args);
+
+    assert_eq!(
+        inputs.object_files,
+        paths(&[
+            "/tmp/rustcXXCyI5/symbols.o",
+            "/app/target/debug/deps/app-1f6b2c3a.app.3c1e2d-cgu.0.rcgu.o",
+            "/app/target/debug/deps/app-1f6b2c3a.4x2hjq8rqv3tqt3n.rcgu.o",
+            "/app/target/debug/deps/libmanganis-8a1b2c3d.rlib",
+            "/home/user/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/x86_64-unknown-linux-gnu/lib/libstd-9c6a2f1e.rlib",
+        ])
+    );
+    assert_eq!(inputs.libraries, vec!["gcc_s", "c"]);
+    assert_eq!(
+        inputs.search_paths,
+        paths(&["/home/user/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/x86_64-unknown-linux-gnu/lib"])
+    );
+    assert!(inputs.static_libraries.is_empty());
+}
+
+#[test]
+fn parses_wasm_ld_command_line() {
+    // Captured from `cargo build --target wasm32-unknown-unknown --release`
+    let args = [
+        "-flavor",
+        "wasm",
+        "--export",
+        "__main_void",
+        "-z",
+        "stack-size=1048576",
+        "--stack-first",
+        "--allow-undefined",
+        "--no-demangle",
+        "--no-entry",
+        "/app/target/wasm32-unknown-unknown/release/deps/app-5d2e.app.1a2b-cgu.0.rcgu.o",
+        "-L",
+        "/app/target/wasm32-unknown-unknown/release/deps",
+        "/app/target/wasm32-unknown-unknown/release/deps/libtest_package_dependency-0c9d.rlib",
+        "/tmp/rustcAb12/libcompiler_builtins-1a2b3c.rlib",
+        "-o",
+        "/app/target/wasm32-unknown-unknown/release/deps/app-5d2e.wasm",
+        "--gc-sections",
+        "-O3",
+    ];
+
+    let inputs = LinkerInputs::parse(args);
+
+    assert_eq!(
+        inputs.object_files,
+        paths(&[
+            "/app/target/wasm32-unknown-unknown/release/deps/app-5d2e.app.1a2b-cgu.0.rcgu.o",
+            "/app/target/wasm32-unknown-unknown/release/deps/libtest_package_dependency-0c9d.rlib",
+            "/tmp/rustcAb12/libcompiler_builtins-1a2b3c.rlib",
+        ])
+    );
+    assert_eq!(
+        inputs.search_paths,
+        paths(&["/app/target/wasm32-unknown-unknown/release/deps"])
+    );
+}
+
+#[test]
+fn parses_macos_command_line() {
+    // Captured from `cargo build` on aarch64-apple-darwin
+    let args = [
+        "-arch",
+        "arm64",
+        "/var/folders/xy/T/rustcQ8fKx2/symbols.o",
+        "/Users/me/My Project/target/debug/deps/app-3b4c.app.9f8e-cgu.0.rcgu.o",
+        "/Users/me/My Project/target/debug/deps/libapp_dep-77aa.rlib",
+        "-framework",
+        "CoreFoundation",
+        "-lSystem",
+        "-lc",
+        "-lm",
+        "-L/Users/me/.rustup/toolchains/stable-aarch64-apple-darwin/lib/rustlib/aarch64-apple-darwin/lib",
+        "-o",
+        "/Users/me/My Project/target/debug/deps/app-3b4c",
+        "-Wl,-dead_strip",
+        "-nodefaultlibs",
+    ];
+
+    let inputs = LinkerInputs::parse(args);
+
+    assert_eq!(
+        inputs.object_files,
+        paths(&[
+            "/var/folders/xy/T/rustcQ8fKx2/symbols.o",
+            "/Users/me/My Project/target/debug/deps/app-3b4c.app.9f8e-cgu.0.rcgu.o",
+            "/Users/me/My Project/target/debug/deps/libapp_dep-77aa.rlib",
+        ])
+    );
+    assert_eq!(inputs.libraries, vec!["System", "c", "m"]);
+    assert_eq!(
+        inputs.search_paths,
+        paths(&["/Users/me/.rustup/toolchains/stable-aarch64-apple-darwin/lib/rustlib/aarch64-apple-darwin/lib"])
+    );
+}
+
+#[test]
+fn splits_gnu_response_files() {
+    // rustc escapes spaces and backslashes in GNU response files
+    let contents = "-m64\n/path/with\\ space/a.o\n\"/quoted path/b.o\"\n'/single quoted/c.rlib'\n\"escaped \\\"quote\\\".o\"\n-Wl,--as-needed\n";
+
+    assert_eq!(
+        ResponseFileSyntax::Gnu.split(contents),
+        vec![
+            "-m64",
+            "/path/with space/a.o",
+            "/quoted path/b.o",
+            "/single quoted/c.rlib",
+            "escaped \"quote\".o",
+            "-Wl,--as-needed",
+        ]
+    );
+}
+
+#[test]
+fn splits_windows_response_files() {
+    // rustc quotes every argument in MSVC response files
+    let contents = "\"/NOLOGO\"\r\n\"C:\\Users\\me\\My Project\\target\\debug\\deps\\app.app.1a-cgu.0.rcgu.o\"\r\n\"C:\\Users\\me\\.rustup\\lib\\libstd-1a2b.rlib\"\r\n\"/LIBPATH:C:\\Program Files\\Microsoft Visual Studio\\VC\\lib\\x64\"\r\n\"kernel32.lib\"\r\n\"a \\\"quoted\\\" name.obj\"\r\n\"trailing\\\\\"\r\n";
+
+    assert_eq!(
+        ResponseFileSyntax::Windows.split(contents),
+        vec![
+            "/NOLOGO",
+            "C:\\Users\\me\\My Project\\target\\debug\\deps\\app.app.1a-cgu.0.rcgu.o",
+            "C:\\Users\\me\\.rustup\\lib\\libstd-1a2b.rlib",
+            "/LIBPATH:C:\\Program Files\\Microsoft Visual Studio\\VC\\lib\\x64",
+            "kernel32.lib",
+            "a \"quoted\" name.obj",
+            "trailing\\",
+        ]
+    );
+}
+
+#[test]
+fn parses_msvc_response_file() {
+    let dir = TestDir::new("msvc");
+    let response_file = dir.join("linker-arguments");
+    let contents = "\"/NOLOGO\"\r\n\"C:\\app\\target\\debug\\deps\\app.app.1a-cgu.0.rcgu.o\"\r\n\"C:\\app\\target\\debug\\deps\\libmanganis-8a1b.rlib\"\r\n\"C:\\app\\native\\assets.lib\"\r\n\"kernel32.lib\"\r\n\"/LIBPATH:C:\\app\\target\\debug\\deps\"\r\n\"/OUT:C:\\app\\target\\debug\\deps\\app.exe\"\r\n";
+
+    // rustc may write response files for link.exe as UTF-16LE
+    let mut utf16 = vec![0xFF, 0xFE];
+    for unit in contents.encode_utf16() {
+        utf16.extend_from_slice(&unit.to_le_bytes());
+    }
+    std::fs::write(&response_file, utf16).unwrap();
+
+    let inputs = LinkerInputs::parse_with_syntax(
+        [format!("@{}", response_file.display())],
+        ResponseFileSyntax::Windows,
+    );
+
+    assert_eq!(
+        inputs.object_files,
+        paths(&[
+            "C:\\app\\target\\debug\\deps\\app.app.1a-cgu.0.rcgu.o",
+            "C:\\app\\target\\debug\\deps\\libmanganis-8a1b.rlib",
+        ])
+    );
+    assert_eq!(
+        inputs.static_libraries,
+        paths(&["C:\\app\\native\\assets.lib", "kernel32.lib"])
+    );
+    assert_eq!(
+        inputs.search_paths,
+        paths(&["C:\\app\\target\\debug\\deps"])
+    );
+}
+
+#[test]
+fn expands_multiple_and_nested_response_files() {
+    let dir = TestDir::new("nested");
+    let first = dir.join("first.rsp");
+    let second = dir.join("second.rsp");
+    let nested = dir.join("nested.rsp");
+
+    std::fs::write(&nested, "/objects/nested.o\n").unwrap();
+    std::fs::write(&first, format!("/objects/first.o\n@{}\n", nested.display())).unwrap();
+    std::fs::write(&second, "/objects/second.rlib -lfoo").unwrap();
+
+    let expanded = expand_response_files(
+        [
+            "-flavor".to_string(),
+            "wasm".to_string(),
+            format!("@{}", first.display()),
+            "/objects/direct.o".to_string(),
+            format!("@{}", second.display()),
+            "@/does/not/exist".to_string(),
+        ],
+        ResponseFileSyntax::Gnu,
+    );
+
+    assert_eq!(
+        expanded,
+        vec![
+            "-flavor",
+            "wasm",
+            "/objects/first.o",
+            "/objects/nested.o",
+            "/objects/direct.o",
+            "/objects/second.rlib",
+            "-lfoo",
+            "@/does/not/exist",
+        ]
+    );
+}
+
+#[test]
+fn resolves_static_libraries_in_search_paths() {
+    let dir = TestDir::new("search-paths");
+    let native = dir.join("native");
+    std::fs::create_dir_all(&native).unwrap();
+    std::fs::write(native.join("libassets.a"), b"!<arch>\n").unwrap();
+    std::fs::write(native.join("exact-name.a"), b"!<arch>\n").unwrap();
+
+    let args = [
+        "/objects/main.o".to_string(),
+        format!("-Wl,-L,{}", native.display()),
+        "-Wl,-Bstatic".to_string(),
+        "-lassets".to_string(),
+        "-l:exact-name.a".to_string(),
+        "-Wl,-Bdynamic".to_string(),
+        "-lmissing".to_string(),
+        "/objects/libvendored.a".to_string(),
+    ];
+
+    let inputs = LinkerInputs::parse_with_syntax(args, ResponseFileSyntax::Gnu);
+
+    assert_eq!(inputs.search_paths, vec![native.clone()]);
+    assert_eq!(inputs.libraries, vec!["assets", ":exact-name.a", "missing"]);
+    assert_eq!(inputs.static_libraries, paths(&["/objects/libvendored.a"]));
+    assert_eq!(
+        inputs.asset_files(),
+        vec![
+            PathBuf::from("/objects/main.o"),
+            PathBuf::from("/objects/libvendored.a"),
+            native.join("libassets.a"),
+            native.join("exact-name.a"),
+        ]
+    );
+}
+
+#[test]
+fn detects_the_syntax_from_the_linker_flavor() {
+    let no_args: &[&str] = &[];
+    let syntax = |linker: &str| ResponseFileSyntax::for_linker(Some(Path::new(linker)), no_args);
+
+    // lld-link uses the Windows rules even when cross compiling from Linux
+    assert_eq!(syntax("/usr/bin/lld-link"), ResponseFileSyntax::Windows);
+    assert_eq!(syntax("LINK.EXE"), ResponseFileSyntax::Windows);
+    assert_eq!(syntax("cc"), ResponseFileSyntax::Gnu);
+    assert_eq!(
+        syntax("x86_64-w64-mingw32-gcc.exe"),
+        ResponseFileSyntax::Gnu
+    );
+    assert_eq!(syntax("wasm-ld"), ResponseFileSyntax::Gnu);
+
+    // rustc passes the flavor to rust-lld
+    let rust_lld = Path::new("rust-lld");
+    assert_eq!(
+        ResponseFileSyntax::for_linker(Some(rust_lld), &["-flavor", "link", "/NOLOGO"]),
+        ResponseFileSyntax::Windows
+    );
+    assert_eq!(
+        ResponseFileSyntax::for_linker(Some(rust_lld), &["-flavor", "wasm"]),
+        ResponseFileSyntax::Gnu
+    );
+    assert_eq!(
+        ResponseFileSyntax::for_linker(None, no_args),
+        ResponseFileSyntax::host()
+    );
+}
+
+#[test]
+fn parses_response_files_with_the_flavor_in_the_arguments() {
+    let dir = TestDir::new("flavor");
+    let response_file = dir.join("linker-arguments");
+    // Backslashes are path separators for lld-link, not escapes
+    std::fs::write(
+        &response_file,
+        "\"C:\\app\\deps\\app.app.1a-cgu.0.rcgu.o\"\r\n\"C:\\app\\native\\assets.lib\"\r\n",
+    )
+    .unwrap();
+
+    let inputs = LinkerInputs::parse([
+        "-flavor".to_string(),
+        "link".to_string(),
+        format!("@{}", response_file.display()),
+    ]);
+
+    assert_eq!(
+        inputs.object_files,
+        paths(&["C:\\app\\deps\\app.app.1a-cgu.0.rcgu.o"])
+    );
+    assert_eq!(
+        inputs.static_libraries,
+        paths(&["C:\\app\\native\\assets.lib"])
+    );
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use common::TestDir;
+use manganis_cli_support::{get_sections_from_object_files, AssetManifestExt};
+use manganis_common::{
+    linker::LinkSection, section::encode_asset, AssetManifest, AssetType, MetadataAsset,
+};
+use object::{
+    write::{Object, StandardSection},
+    Architecture, BinaryFormat, Endianness, SectionKind,
+};
+use std::path::Path;
+
+fn metadata(key: &str) -> AssetType {
+    AssetType::Metadata(MetadataAsset::new(key, "value"))
+}
+
+// An ELF object file with the records of some assets in the manganis section
+fn object_file(package: &str, keys: &[&str]) -> Vec<u8> {
+    let mut object = Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let section = object.add_section(
+        Vec::new(),
+        LinkSection::WASM.link_section.as_bytes().to_vec(),
+        SectionKind::Data,
+    );
+    for key in keys {
+        object.append_section_data(section, &encode_asset(package, &metadata(key)), 1);
+    }
+    // Some unrelated data the collector should ignore
+    let data = object.section_id(StandardSection::Data);
+    object.append_section_data(data, b"not an asset", 1);
+    object.write().unwrap()
+}
+
+// Writes a GNU archive. Thin archives only store the names of the members, which must be next to the archive
+fn archive(path: &Path, members: &[(&str, &[u8])], thin: bool) {
+    let mut data = if thin {
+        b"!<thin>\n".to_vec()
+    } else {
+        b"!<arch>\n".to_vec()
+    };
+    for (name, member) in members {
+        let name = format!("{name}/");
+        data.extend(
+            format!(
+                "{name:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                0,
+                0,
+                0,
+                644,
+                member.len()
+            )
+            .as_bytes(),
+        );
+        if thin {
+            std::fs::write(path.parent().unwrap().join(&name[..name.len() - 1]), member).unwrap();
+        } else {
+            data.extend_from_slice(member);
+            if member.len() % 2 == 1 {
+                data.push(b'\n');
+            }
+        }
+    }
+    std::fs::write(path, data).unwrap();
+}
+
+fn keys(manifest: &AssetManifest) -> Vec<&str> {
+    manifest
+        .assets()
+        .iter()
+        .map(|asset| match asset {
+            AssetType::Metadata(metadata) => metadata.key(),
+            _ => panic!("unexpected asset {asset:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn collects_from_objects_and_archives() {
+    let dir = TestDir::new("archives");
+    std::fs::write(dir.join("main.o"), object_file("app", &["title"])).unwrap();
+    archive(
+        &dir.join("libdep.rlib"),
+        &[
+            ("lib.rmeta", b"metadata"),
+            ("dep.o", &object_file("dep", &["description", "author"])),
+        ],
+        false,
+    );
+
+    let manifest =
+        AssetManifest::try_load_from_objects(vec![dir.join("main.o"), dir.join("libdep.rlib")])
+            .unwrap();
+    assert_eq!(keys(&manifest), ["title", "description", "author"]);
+    let packages: Vec<_> = manifest
+        .assets_with_packages()
+        .map(|(_, package)| package)
+        .collect();
+    assert_eq!(packages, [Some("app"), Some("dep"), Some("dep")]);
+}
+
+#[test]
+fn collects_from_thin_archives() {
+    let dir = TestDir::new("thin");
+    archive(
+        &dir.join("libthin.a"),
+        &[
+            ("first.o", &object_file("first", &["title"])),
+            ("second.o", &object_file("second", &["description"])),
+        ],
+        true,
+    );
+
+    let manifest = AssetManifest::try_load_from_objects(vec![dir.join("libthin.a")]).unwrap();
+    assert_eq!(keys(&manifest), ["title", "description"]);
+}
+
+#[test]
+fn skips_members_that_are_not_objects() {
+    let dir = TestDir::new("skip");
+    archive(
+        &dir.join("libmixed.a"),
+        &[
+            ("notes.txt", b"some text that is not an object file"),
+            ("broken.o", b"\x7fELF but not really"),
+            ("dep.o", &object_file("dep", &["title"])),
+        ],
+        false,
+    );
+    // Missing files are skipped too
+    let paths = vec![dir.join("missing.o"), dir.join("libmixed.a")];
+
+    let manifest = AssetManifest::try_load_from_objects(paths).unwrap();
+    assert_eq!(keys(&manifest), ["title"]);
+}
+
+#[test]
+fn collects_from_bitcode() {
+    let dir = TestDir::new("bitcode");
+    let records = [
+        encode_asset("dep", &metadata("title")),
+        encode_asset("dep", &metadata("description")),
+    ];
+
+    // Bitcode is a stream of bits, so the constants may start at any bit offset
+    for shift in 0..8 {
+        let mut stream = b"BC\xC0\xDE some bitcode".to_vec();
+        for record in &records {
+            stream.extend_from_slice(record);
+            stream.extend_from_slice(b"more bitcode");
+        }
+        let mut shifted = stream[..4].to_vec();
+        let mut carry = 0u8;
+        for byte in &stream[4..] {
+            shifted.push((byte << shift) | carry);
+            carry = if shift == 0 { 0 } else { byte >> (8 - shift) };
+        }
+        shifted.push(carry);
+
+        let path = dir.join(format!("dep-{shift}.o"));
+        std::fs::write(&path, &shifted).unwrap();
+        let sections = get_sections_from_object_files(vec![path]);
+        assert_eq!(sections, [records.concat()], "shift {shift}");
+    }
+}
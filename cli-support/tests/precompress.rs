@@ -0,0 +1,105 @@
+mod common;
+
+use common::{use_config, TestDir};
+use manganis_cli_support::{
+    precompress_file, AssetManifest, AssetManifestExt, AssetType, Config, CssOptions, FileAsset,
+    FileOptions, FileSource, PrecompressConfig,
+};
+use manganis_common::ContentEncoding;
+use std::io::Read;
+
+// Every test in this file collects assets with precompression turned on
+fn precompress_enabled() {
+    use_config(Config::default().with_precompress(PrecompressConfig::default().with_enabled(true)));
+}
+
+#[test]
+fn writes_variants_for_files_above_the_threshold() {
+    let dir = TestDir::new("variants");
+    let css = ".button { color: red; }\n".repeat(100);
+    let large = dir.join("large.css");
+    std::fs::write(&large, &css).unwrap();
+    let small = dir.join("small.css");
+    std::fs::write(&small, ".a{}").unwrap();
+
+    let written = precompress_file(&large, 1024).unwrap();
+    assert_eq!(written, ContentEncoding::ALL);
+    assert!(dir.join("large.css.br").is_file());
+
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(dir.join("large.css.gz")).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, css);
+
+    assert!(precompress_file(&small, 1024).unwrap().is_empty());
+    assert!(!dir.join("small.css.gz").exists());
+}
+
+#[test]
+fn rewrites_variants_that_are_older_than_the_file() {
+    let dir = TestDir::new("stale");
+    let path = dir.join("tailwind.css");
+    std::fs::write(&path, ".old { color: red; }\n".repeat(100)).unwrap();
+    precompress_file(&path, 1024).unwrap();
+
+    // The file is written again under the same name, like tailwind.css on every build
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let css = ".new { color: blue; }\n".repeat(100);
+    std::fs::write(&path, &css).unwrap();
+    assert_eq!(precompress_file(&path, 1024).unwrap(), ContentEncoding::ALL);
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(dir.join("tailwind.css.gz")).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, css);
+
+    // Variants of a file that shrank below the threshold are removed
+    std::fs::write(&path, ".a{}").unwrap();
+    assert!(precompress_file(&path, 1024).unwrap().is_empty());
+    assert!(!dir.join("tailwind.css.br").exists());
+    assert!(!dir.join("tailwind.css.gz").exists());
+}
+
+#[test]
+fn is_off_by_default() {
+    assert!(!PrecompressConfig::default().enabled());
+    assert!(!Config::default().precompress().enabled());
+    let config: PrecompressConfig = toml::from_str("min_size = 10").unwrap();
+    assert!(!config.enabled());
+}
+
+#[test]
+fn records_the_variants_in_the_manifest() {
+    precompress_enabled();
+    let dir = TestDir::new("manifest");
+    let large = dir.join("large.css");
+    let css: String = (0..100)
+        .map(|i| format!(".button-{i} {{ color: red; }}\n"))
+        .collect();
+    std::fs::write(&large, css).unwrap();
+    let small = dir.join("small.css");
+    std::fs::write(&small, ".a { color: red; }").unwrap();
+
+    let options = CssOptions::new(false);
+    let assets: Vec<_> = [large, small]
+        .into_iter()
+        .map(|path| {
+            FileAsset::new(FileSource::Local(path)).with_options(FileOptions::Css(options.clone()))
+        })
+        .collect();
+    let mut manifest = AssetManifest::new(assets.iter().cloned().map(AssetType::File).collect());
+    manifest.process_static_assets_to(dir.join("out")).unwrap();
+
+    let large_name = assets[0].location().unique_name();
+    assert_eq!(manifest.precompressed(large_name), ContentEncoding::ALL);
+    for encoding in ContentEncoding::ALL {
+        assert!(dir
+            .join("out")
+            .join(encoding.variant_name(large_name))
+            .is_file());
+    }
+    assert!(manifest
+        .precompressed(assets[1].location().unique_name())
+        .is_empty());
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{use_config, TestDir};
+use manganis_cli_support::{AssetReport, ManifestReport, REPORT_SCHEMA_VERSION};
+use manganis_common::{
+    section::{decode_section, encode_asset},
+    AssetManifest, AssetType, Config, ContentEncoding, CssOptions, FileAsset, FileOptions,
+    FileSource, MetadataAsset, TailwindAsset,
+};
+
+// Every test in this file serves the assets from `/assets`
+fn serve_from_assets() {
+    use_config(Config::default().with_assets_serve_location("/assets"));
+}
+
+// A manifest with a stylesheet from `app` and a tailwind and metadata asset from `dep`
+fn manifest(dir: &std::path::Path) -> (AssetManifest, FileAsset) {
+    serve_from_assets();
+    let path = dir.join("style.css");
+    std::fs::write(&path, "body { color: red; }").unwrap();
+    let css = FileAsset::new(FileSource::Local(path))
+        .with_options(FileOptions::Css(CssOptions::new(false)));
+
+    let mut section = encode_asset("app", &AssetType::File(css.clone()));
+    section.extend(encode_asset(
+        "dep",
+        &AssetType::Tailwind(TailwindAsset::new("flex p-4")),
+    ));
+    section.extend(encode_asset(
+        "dep",
+        &AssetType::Metadata(MetadataAsset::new("title", "My App")),
+    ));
+    let records = decode_section(&section).records;
+    (AssetManifest::from_records(records), css)
+}
+
+#[test]
+fn describes_every_asset() {
+    let dir = TestDir::new("describe");
+    let (manifest, css) = manifest(&dir);
+    let unique_name = css.location().unique_name().to_string();
+
+    let report = ManifestReport::new(&manifest);
+    assert_eq!(report.schema_version, REPORT_SCHEMA_VERSION);
+    assert_eq!(
+        report.assets,
+        [
+            AssetReport::File {
+                package: Some("app".to_string()),
+                source: dir.join("style.css").display().to_string(),
+                served_path: Some(format!("/assets/{unique_name}")),
+                unique_name,
+                file_type: "css".to_string(),
+                extension: Some("css".to_string()),
+                options: css.options().to_string(),
+                url_encoded: false,
+                embedded: false,
+                processed_size: None,
+                precompressed: Vec::new(),
+            },
+            AssetReport::Tailwind {
+                package: Some("dep".to_string()),
+                classes: "flex p-4".to_string(),
+            },
+            AssetReport::Metadata {
+                package: Some("dep".to_string()),
+                key: "title".to_string(),
+                value: "My App".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn writes_the_documented_json_schema() {
+    let dir = TestDir::new("json");
+    let (manifest, _) = manifest(&dir);
+    let report = ManifestReport::new(&manifest);
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["schema_version"], REPORT_SCHEMA_VERSION);
+    let kinds: Vec<_> = json["assets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|asset| asset["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["file", "tailwind", "metadata"]);
+    let file = &json["assets"][0];
+    for field in [
+        "package",
+        "source",
+        "unique_name",
+        "served_path",
+        "file_type",
+        "extension",
+        "options",
+        "url_encoded",
+        "embedded",
+        "processed_size",
+        "precompressed",
+    ] {
+        assert!(file.get(field).is_some(), "missing {field} in {file}");
+    }
+
+    // The report reads back from its JSON
+    let read: ManifestReport = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(read, report);
+}
+
+#[test]
+fn fills_in_processed_sizes() {
+    let dir = TestDir::new("sizes");
+    let (manifest, css) = manifest(&dir);
+    let unique_name = css.location().unique_name().to_string();
+    let out = dir.join("out");
+    std::fs::create_dir_all(&out).unwrap();
+    std::fs::write(out.join(&unique_name), vec![b' '; 2048]).unwrap();
+    std::fs::write(
+        out.join(ContentEncoding::Brotli.variant_name(&unique_name)),
+        "",
+    )
+    .unwrap();
+
+    let report = ManifestReport::new(&manifest).with_processed_sizes(&out);
+    let AssetReport::File {
+        processed_size,
+        precompressed,
+        ..
+    } = &report.assets[0]
+    else {
+        panic!("expected a file, found {:?}", report.assets[0]);
+    };
+    assert_eq!(*processed_size, Some(2048));
+    assert_eq!(precompressed, &[ContentEncoding::Brotli]);
+    assert!(report.to_table().contains("2.0 KiB (br)"));
+}
+
+#[test]
+fn renders_an_aligned_table() {
+    let dir = TestDir::new("table");
+    let (manifest, css) = manifest(&dir);
+    let table = ManifestReport::new(&manifest).to_table();
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines.len(), 4, "{table}");
+
+    assert!(lines[0].starts_with("KIND"));
+    assert!(lines[1].starts_with("file"));
+    assert!(lines[1].contains(&format!("/assets/{}", css.location().unique_name())));
+    assert!(lines[2].starts_with("tailwind"));
+    assert!(lines[2].contains("tailwind.css"));
+    assert!(lines[3].starts_with("metadata"));
+    assert!(lines[3].contains("title = My App"));
+
+    // Every column starts at the same offset in each row
+    let package_column = lines[0].find("PACKAGE").unwrap();
+    assert_eq!(lines[1].find("app"), Some(package_column));
+    assert_eq!(lines[2].find("dep"), Some(package_column));
+    assert_eq!(lines[3].find("dep"), Some(package_column));
+    let source_column = lines[0].find("SOURCE").unwrap();
+    assert_eq!(lines[2].find("flex p-4"), Some(source_column));
+    assert_eq!(table, ManifestReport::new(&manifest).to_string());
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::TestDir;
+use manganis_cli_support::{AssetManifest, AssetServer, ContentEncoding};
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+fn start(server: AssetServer) -> SocketAddr {
+    let server = server.bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+    addr
+}
+
+fn get(addr: SocketAddr, path: &str, headers: &[&str]) -> String {
+    request(addr, "GET", path, headers)
+}
+
+fn request(addr: SocketAddr, method: &str, path: &str, headers: &[&str]) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut request = format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\n");
+    for header in headers {
+        request.push_str(header);
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    response.lines().find_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        header.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+#[test]
+fn serves_files_with_content_type_and_etag() {
+    let dir = TestDir::new("etag");
+    std::fs::write(dir.join("style.css"), "body { color: red; }").unwrap();
+    let addr = start(AssetServer::new(&dir).serve_location("/assets"));
+
+    let response = get(addr, "/assets/style.css", &[]);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert_eq!(header(&response, "content-type"), Some("text/css"));
+    assert_eq!(header(&response, "cache-control"), Some("no-cache"));
+    assert!(response.ends_with("body { color: red; }"));
+
+    let etag = header(&response, "etag").unwrap();
+    let response = get(
+        addr,
+        "/assets/style.css",
+        &[&format!("If-None-Match: {etag}")],
+    );
+    assert!(response.starts_with("HTTP/1.1 304"), "{response}");
+}
+
+#[test]
+fn serves_precompressed_variants() {
+    let dir = TestDir::new("precompressed");
+    std::fs::write(dir.join("app.js"), "console.log('hello')").unwrap();
+    std::fs::write(dir.join("app.js.gz"), "gzipped").unwrap();
+    // A leftover variant that is not in the manifest is never served
+    std::fs::write(dir.join("app.js.br"), "stale").unwrap();
+    let mut manifest = AssetManifest::default();
+    manifest.set_precompressed("app.js", vec![ContentEncoding::Gzip]);
+    let addr = start(AssetServer::new(&dir).manifest(&manifest));
+
+    let response = get(addr, "/app.js", &["Accept-Encoding: gzip, deflate"]);
+    assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+    assert_eq!(header(&response, "vary"), Some("Accept-Encoding"));
+    assert!(response.ends_with("gzipped"));
+
+    let response = get(addr, "/app.js", &["Accept-Encoding: br"]);
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert!(response.ends_with("console.log('hello')"));
+
+    let response = get(addr, "/app.js", &["Accept-Encoding: gzip;q=0"]);
+    assert_eq!(header(&response, "content-encoding"), None);
+
+    // Without a manifest only the file itself is served
+    let addr = start(AssetServer::new(&dir));
+    let response = get(addr, "/app.js", &["Accept-Encoding: gzip"]);
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert_eq!(header(&response, "vary"), None);
+}
+
+#[test]
+fn does_not_serve_files_outside_of_the_directory() {
+    let dir = TestDir::new("traversal");
+    std::fs::create_dir_all(dir.join("public")).unwrap();
+    std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+    let addr = start(AssetServer::new(dir.join("public")));
+
+    for path in [
+        "/../secret.txt",
+        "/%2e%2e/secret.txt",
+        "/sub/../../secret.txt",
+    ] {
+        let response = get(addr, path, &[]);
+        assert!(response.starts_with("HTTP/1.1 404"), "{path}: {response}");
+    }
+}
+
+#[test]
+fn sends_the_reason_phrase_of_each_status() {
+    let dir = TestDir::new("status");
+    std::fs::write(dir.join("index.html"), "<html></html>").unwrap();
+    let addr = start(AssetServer::new(&dir));
+    let status_line = |response: &str| response.lines().next().unwrap_or_default().to_string();
+
+    let response = get(addr, "/index.html", &[]);
+    assert_eq!(status_line(&response), "HTTP/1.1 200 OK");
+
+    let etag = header(&response, "etag").unwrap();
+    let response = get(addr, "/index.html", &[&format!("If-None-Match: {etag}")]);
+    assert_eq!(status_line(&response), "HTTP/1.1 304 Not Modified");
+
+    let response = get(addr, "/missing.html", &[]);
+    assert_eq!(status_line(&response), "HTTP/1.1 404 Not Found");
+
+    let response = request(addr, "POST", "/index.html", &[]);
+    assert_eq!(status_line(&response), "HTTP/1.1 405 Method Not Allowed");
+    assert_eq!(header(&response, "allow"), Some("GET, HEAD"));
+
+    // HEAD requests get the headers without the body
+    let response = request(addr, "HEAD", "/index.html", &[]);
+    assert_eq!(status_line(&response), "HTTP/1.1 200 OK");
+    assert_eq!(header(&response, "content-length"), Some("13"));
+    assert!(response.ends_with("\r\n\r\n"));
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "watch")]
+
+mod common;
+
+use common::{use_config, TestDir};
+
+use manganis_cli_support::{AssetManifestExt, AssetWatcher};
+use manganis_common::{
+    AssetManifest, AssetType, Config, FileAsset, FileSource, FolderAsset, PackageConfig,
+};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Every test in this file uses the default config, whatever the global config is
+fn default_config() {
+    use_config(Config::default());
+}
+
+#[test]
+fn reports_renamed_files() {
+    default_config();
+    let dir = TestDir::new("file");
+    let source = dir.join("notes.txt");
+    std::fs::write(&source, "first").unwrap();
+    let file = FileAsset::new(FileSource::Local(source.clone()));
+    let old_unique_name = file.location().unique_name().to_string();
+
+    let out = dir.join("out");
+    let mut manifest = AssetManifest::new(vec![AssetType::File(file)]);
+    manifest.process_static_assets_to(&out).unwrap();
+    let mut watcher = AssetWatcher::new(manifest, &out).unwrap();
+
+    std::fs::write(&source, "second").unwrap();
+    let changes = watcher.wait_timeout(TIMEOUT).unwrap();
+    assert_eq!(changes.len(), 1, "{changes:?}");
+    let change = &changes[0];
+    assert_eq!(change.source, source);
+    assert_eq!(change.old_unique_name, old_unique_name);
+    assert_ne!(change.new_unique_name, old_unique_name);
+    assert_eq!(
+        std::fs::read_to_string(out.join(&change.new_unique_name)).unwrap(),
+        "second"
+    );
+
+    // The manifest uses the new name
+    let AssetType::File(file) = &watcher.manifest().assets()[0] else {
+        panic!("expected a file asset");
+    };
+    assert_eq!(file.location().unique_name(), change.new_unique_name);
+}
+
+#[test]
+fn moves_every_file_of_a_changed_folder() {
+    default_config();
+    let dir = TestDir::new("folder");
+    std::fs::create_dir_all(dir.join("static")).unwrap();
+    std::fs::write(dir.join("static/a.txt"), "a").unwrap();
+    std::fs::write(dir.join("static/b.txt"), "b").unwrap();
+    let folder = FolderAsset::from_dir("static", &dir, &PackageConfig::default()).unwrap();
+
+    let out = dir.join("out");
+    let mut manifest = AssetManifest::new(vec![AssetType::Folder(folder)]);
+    manifest.process_static_assets_to(&out).unwrap();
+    let mut watcher = AssetWatcher::new(manifest, &out).unwrap();
+
+    std::fs::write(dir.join("static/a.txt"), "changed").unwrap();
+    let mut changes = watcher.wait_timeout(TIMEOUT).unwrap();
+    changes.sort_by(|a, b| a.source.cmp(&b.source));
+    assert_eq!(changes.len(), 2, "{changes:?}");
+
+    // The folder gets a new name, so the unchanged file moves with it
+    for (change, (name, content)) in changes.iter().zip([("a.txt", "changed"), ("b.txt", "b")]) {
+        assert_eq!(change.source, dir.join("static").join(name));
+        assert_ne!(change.old_unique_name, change.new_unique_name);
+        assert!(change.new_unique_name.ends_with(name));
+        assert_eq!(
+            std::fs::read_to_string(out.join(&change.new_unique_name)).unwrap(),
+            content
+        );
+    }
+}
+
+#[test]
+fn ignores_files_that_are_not_assets() {
+    default_config();
+    let dir = TestDir::new("unrelated");
+    let source = dir.join("notes.txt");
+    std::fs::write(&source, "notes").unwrap();
+    let manifest = AssetManifest::new(vec![AssetType::File(FileAsset::new(FileSource::Local(
+        source,
+    )))]);
+    let mut watcher = AssetWatcher::new(manifest, dir.join("out")).unwrap();
+
+    std::fs::write(dir.join("other.txt"), "other").unwrap();
+    let changes = watcher.wait_timeout(Duration::from_millis(500)).unwrap();
+    assert_eq!(changes, []);
+}
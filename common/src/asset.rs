@@ -10,7 +10,10 @@ use base64::Engine;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::{cache::manifest_dir, Config, FileOptions};
+use crate::{
+    bundled_files, cache::manifest_dir, is_compressible, read_remote_asset, remote_asset, Config,
+    FileOptions, FolderAsset, Integrity,
+};
 
 /// The type of asset
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
@@ -21,6 +24,8 @@ pub enum AssetType {
     Tailwind(TailwindAsset),
     /// A metadata asset
     Metadata(MetadataAsset),
+    /// A folder of file assets
+    Folder(FolderAsset),
 }
 
 /// The source of a file asset
@@ -51,7 +56,7 @@ impl FileSource {
     pub fn last_segment(&self) -> &str {
         match self {
             Self::Local(path) => path.file_name().unwrap().to_str().unwrap(),
-            Self::Remote(url) => url.path_segments().unwrap().last().unwrap(),
+            Self::Remote(url) => url.path_segments().unwrap().next_back().unwrap(),
         }
     }
 
@@ -59,19 +64,10 @@ impl FileSource {
     pub fn extension(&self) -> Option<String> {
         match self {
             Self::Local(path) => path.extension().map(|e| e.to_str().unwrap().to_string()),
-            Self::Remote(url) => reqwest::blocking::get(url.as_str())
-                .ok()
-                .and_then(|request| {
-                    request
-                        .headers()
-                        .get("content-type")
-                        .and_then(|content_type| {
-                            content_type
-                                .to_str()
-                                .ok()
-                                .map(|ty| ext_of_mime(ty).to_string())
-                        })
-                }),
+            Self::Remote(url) => remote_asset(url)
+                .ok()?
+                .content_type()
+                .map(|ty| ext_of_mime(ty).to_string()),
         }
     }
 
@@ -79,14 +75,7 @@ impl FileSource {
     pub fn mime_type(&self) -> Option<String> {
         match self {
             Self::Local(path) => get_mime_from_path(path).ok().map(|mime| mime.to_string()),
-            Self::Remote(url) => reqwest::blocking::get(url.as_str())
-                .ok()
-                .and_then(|request| {
-                    request
-                        .headers()
-                        .get("content-type")
-                        .and_then(|content_type| Some(content_type.to_str().ok()?.to_string()))
-                }),
+            Self::Remote(url) => remote_asset(url).ok()?.content_type().map(String::from),
         }
     }
 
@@ -105,19 +94,7 @@ impl FileSource {
                             .map(|created| format!("{:?}", created))
                     })
             }),
-            Self::Remote(url) => reqwest::blocking::get(url.as_str())
-                .ok()
-                .and_then(|request| {
-                    request
-                        .headers()
-                        .get("last-modified")
-                        .and_then(|last_modified| {
-                            last_modified
-                                .to_str()
-                                .ok()
-                                .map(|last_modified| last_modified.to_string())
-                        })
-                }),
+            Self::Remote(url) => remote_asset(url).ok()?.last_modified().map(String::from),
         }
     }
 }
@@ -148,7 +125,7 @@ fn ext_of_mime(mime: &str) -> &str {
         "font/ttf" => "ttf",
         "font/woff" => "woff",
         "font/woff2" => "woff2",
-        other => other.split('/').last().unwrap_or_default(),
+        other => other.split('/').next_back().unwrap_or_default(),
     }
 }
 
@@ -211,6 +188,8 @@ pub fn get_mime_from_ext(extension: Option<&str>) -> &'static str {
 pub struct FileLocation {
     unique_name: String,
     source: FileSource,
+    #[serde(default)]
+    integrity: Option<Integrity>,
 }
 
 impl FileLocation {
@@ -224,36 +203,31 @@ impl FileLocation {
         &self.source
     }
 
+    /// Returns the hash the content of the file must match, if one was set
+    pub fn integrity(&self) -> Option<&Integrity> {
+        self.integrity.as_ref()
+    }
+
     /// Reads the file to a string
     pub fn read_to_string(&self) -> anyhow::Result<String> {
-        match &self.source {
-            FileSource::Local(path) => Ok(std::fs::read_to_string(path).with_context(|| {
-                format!("Failed to read file from location: {}", path.display())
-            })?),
-            FileSource::Remote(url) => {
-                let response = reqwest::blocking::get(url.as_str())
-                    .with_context(|| format!("Failed to asset from url: {}", url.as_str()))?;
-                Ok(response.text().with_context(|| {
-                    format!("Failed to read text for asset from url: {}", url.as_str())
-                })?)
-            }
-        }
+        String::from_utf8(self.read_to_bytes()?)
+            .with_context(|| format!("The asset {} is not valid UTF-8", self.source))
     }
 
-    /// Reads the file to bytes
+    /// Reads the file to bytes. If the location has an integrity, the content is checked against it
     pub fn read_to_bytes(&self) -> anyhow::Result<Vec<u8>> {
-        match &self.source {
-            FileSource::Local(path) => Ok(std::fs::read(path).with_context(|| {
+        let bytes = match &self.source {
+            FileSource::Local(path) => std::fs::read(path).with_context(|| {
                 format!("Failed to read file from location: {}", path.display())
-            })?),
-            FileSource::Remote(url) => {
-                let response = reqwest::blocking::get(url.as_str())
-                    .with_context(|| format!("Failed to asset from url: {}", url.as_str()))?;
-                Ok(response.bytes().map(|b| b.to_vec()).with_context(|| {
-                    format!("Failed to read text for asset from url: {}", url.as_str())
-                })?)
-            }
+            })?,
+            FileSource::Remote(url) => read_remote_asset(url)?,
+        };
+        if let Some(integrity) = &self.integrity {
+            integrity
+                .verify(&bytes)
+                .with_context(|| format!("Failed to verify the asset {}", self.source))?;
         }
+        Ok(bytes)
     }
 }
 
@@ -268,6 +242,10 @@ pub enum AssetError {
     NotFile(PathBuf),
     /// Unknown IO error
     IO(PathBuf, std::io::Error),
+    /// The path exists but is not a directory
+    NotDirectory(PathBuf),
+    /// The glob pattern is invalid or did not match any files
+    InvalidGlob(String, String),
 }
 
 impl Display for AssetError {
@@ -283,7 +261,11 @@ impl Display for AssetError {
             AssetError::NotFile(absolute_path) =>
                 write!(f, "`{}` is not a file, please choose a valid asset.\nAny relative paths are resolved relative to the manifest directory.", absolute_path.display()),
             AssetError::IO(absolute_path, err) =>
-                write!(f, "unknown error when accessing `{}`: \n{}", absolute_path.display(), err)
+                write!(f, "unknown error when accessing `{}`: \n{}", absolute_path.display(), err),
+            AssetError::NotDirectory(absolute_path) =>
+                write!(f, "`{}` is not a directory, please choose a valid folder.\nAny relative paths are resolved relative to the manifest directory.", absolute_path.display()),
+            AssetError::InvalidGlob(pattern, error) =>
+                write!(f, "invalid glob pattern `{}`: {}", pattern, error),
         }
     }
 }
@@ -292,11 +274,18 @@ impl FromStr for FileSource {
     type Err = AssetError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_relative_to(s, manifest_dir())
+    }
+}
+
+impl FileSource {
+    /// Parses a URL or a path. Relative paths are resolved from the given directory
+    pub fn parse_relative_to(s: &str, base_dir: impl Into<PathBuf>) -> Result<Self, AssetError> {
         match Url::parse(s) {
             Ok(url) => Ok(Self::Remote(url)),
             Err(_) => {
-                let manifest_dir = manifest_dir();
-                let path = manifest_dir.join(PathBuf::from(s));
+                let base_dir = base_dir.into();
+                let path = base_dir.join(PathBuf::from(s));
                 let is_absolute = PathBuf::from(s).is_absolute();
 
                 match path.canonicalize() {
@@ -309,7 +298,7 @@ impl FromStr for FileSource {
                     }
                     // relative path does not exist
                     Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                        Err(AssetError::NotFoundRelative(manifest_dir, s.into()))
+                        Err(AssetError::NotFoundRelative(base_dir, s.into()))
                     }
                     // other error
                     Err(e) => Err(AssetError::IO(path, e)),
@@ -319,12 +308,28 @@ impl FromStr for FileSource {
     }
 }
 
+/// Joins the serve location recorded when an asset was collected and its unique name. Assets written by older versions of manganis did not record the location, so they use the current config
+pub(crate) fn served_location(serve_location: Option<&str>, unique_name: &str) -> String {
+    match serve_location {
+        Some(serve_location) => format!("{serve_location}{unique_name}"),
+        None => Config::current().asset_location(unique_name),
+    }
+}
+
 /// A file asset
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
 pub struct FileAsset {
     location: FileLocation,
     options: FileOptions,
     url_encoded: bool,
+    #[serde(default)]
+    embedded: bool,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    precompress_min_size: Option<u64>,
+    #[serde(default)]
+    serve_location: Option<String>,
 }
 
 impl Display for FileAsset {
@@ -334,12 +339,16 @@ impl Display for FileAsset {
         } else {
             ""
         };
+        let embedded = if self.embedded { " [embedded]" } else { "" };
+        let dev = if self.dev { " [dev]" } else { "" };
         write!(
             f,
-            "{} [{}]{}",
+            "{} [{}]{}{}{}",
             self.location.source(),
             self.options,
-            url_encoded
+            url_encoded,
+            embedded,
+            dev
         )
     }
 }
@@ -348,14 +357,21 @@ impl FileAsset {
     /// Creates a new file asset
     pub fn new(source: FileSource) -> Self {
         let options = FileOptions::default_for_extension(source.extension().as_deref());
+        let config = Config::current();
+        let precompress_min_size = precompress_min_size(&config, &options);
 
         let mut myself = Self {
             location: FileLocation {
                 unique_name: Default::default(),
                 source,
+                integrity: None,
             },
             options,
             url_encoded: false,
+            embedded: false,
+            dev: config.dev(),
+            precompress_min_size,
+            serve_location: Some(config.asset_location("")),
         };
 
         myself.regenerate_unique_name();
@@ -367,8 +383,12 @@ impl FileAsset {
     pub fn with_options(self, options: FileOptions) -> Self {
         let mut myself = Self {
             location: self.location,
+            precompress_min_size: precompress_min_size(&Config::current(), &options),
             options,
             url_encoded: false,
+            embedded: false,
+            dev: self.dev,
+            serve_location: self.serve_location,
         };
 
         myself.regenerate_unique_name();
@@ -386,7 +406,40 @@ impl FileAsset {
         self.url_encoded
     }
 
+    /// Set whether the processed file is embedded in the binary
+    pub fn set_embedded(&mut self, embedded: bool) {
+        self.embedded = embedded;
+    }
+
+    /// Returns whether the processed file is embedded in the binary. Embedded assets are still collected, so they can also be served
+    pub fn embedded(&self) -> bool {
+        self.embedded
+    }
+
+    /// Set the hash the content of the file must match. The content is checked every time the file is read
+    pub fn set_integrity(&mut self, integrity: Option<Integrity>) {
+        self.location.integrity = integrity;
+    }
+
+    /// Returns whether the asset was collected in dev mode. Dev assets are served from the original file without processing. See [`Config::with_dev`]
+    pub fn dev(&self) -> bool {
+        self.dev
+    }
+
+    /// Returns the size a processed file must have before `.br` and `.gz` variants are written next to it, or `None` if no variants are written. See [`Config::with_precompress`]
+    pub fn precompress_min_size(&self) -> Option<u64> {
+        self.precompress_min_size
+    }
+
+    /// Replaces the unique name without hashing the asset. Used for files in a folder, which keep their relative path
+    pub(crate) fn with_unique_name(mut self, unique_name: String) -> Self {
+        self.location.unique_name = unique_name;
+        self
+    }
+
     /// Returns the location where the file asset will be served from
+    ///
+    /// The location is recorded in the config of the build that collected the asset, so a build tool gets the same location the application uses.
     pub fn served_location(&self) -> String {
         if self.url_encoded {
             let data = self.location.read_to_bytes().unwrap();
@@ -394,10 +447,7 @@ impl FileAsset {
             let mime = self.location.source.mime_type().unwrap();
             format!("data:{mime};base64,{data}")
         } else {
-            let config = Config::current();
-            let root = config.assets_serve_location();
-            let unique_name = self.location.unique_name();
-            format!("{root}{unique_name}")
+            served_location(self.serve_location.as_deref(), self.location.unique_name())
         }
     }
 
@@ -411,6 +461,15 @@ impl FileAsset {
         &self.options
     }
 
+    /// Returns the local files the processed asset is built from. This is the source, and for bundled javascript every file it imports with a relative path
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        match (&self.location.source, &self.options) {
+            (FileSource::Local(path), FileOptions::Js(js)) if js.bundle() => bundled_files(path),
+            (FileSource::Local(path), _) => vec![path.clone()],
+            (FileSource::Remote(_), _) => Vec::new(),
+        }
+    }
+
     /// Returns the options for the file asset mutably
     pub fn with_options_mut(&mut self, f: impl FnOnce(&mut FileOptions)) {
         f(&mut self.options);
@@ -418,40 +477,48 @@ impl FileAsset {
     }
 
     /// Regenerates the unique name of the file asset
-    fn regenerate_unique_name(&mut self) {
+    ///
+    /// The name depends on when the source was last updated, so call this after the source changed to get the name the macro would generate now.
+    pub fn regenerate_unique_name(&mut self) {
         const MAX_PATH_LENGTH: usize = 128;
         const HASH_SIZE: usize = 16;
 
-        let manifest_dir = manifest_dir();
-        let last_segment = self
+        // Only alphanumeric characters are kept, so the segment has no extension
+        let mut file_name = self
             .location
             .source
             .last_segment()
             .chars()
             .filter(|c| c.is_alphanumeric())
             .collect::<String>();
-        let path = manifest_dir.join(last_segment);
-        let updated = self.location.source.last_updated();
-        let extension = self
-            .options
-            .extension()
-            .map(|e| format!(".{e}"))
-            .unwrap_or_default();
+        // Dev assets are served unprocessed under a name that stays the same when the file or its options change.
+        // The name still ends with a hash of the source, because files with the same name in different directories would collide in the output directory
+        let updated = (!self.dev)
+            .then(|| self.location.source.last_updated())
+            .flatten();
+        let extension = if self.dev {
+            self.location.source.extension()
+        } else {
+            self.options.extension().map(String::from)
+        }
+        .map(|e| format!(".{e}"))
+        .unwrap_or_default();
         let extension_and_hash_size = extension.len() + HASH_SIZE;
-        let mut file_name = path
-            .file_stem()
-            .unwrap()
-            .to_string_lossy()
-            .chars()
-            .filter(|c| c.is_alphanumeric())
-            .collect::<String>();
         // If the file name is too long, we need to truncate it
         if file_name.len() + extension_and_hash_size > MAX_PATH_LENGTH {
             file_name = file_name[..MAX_PATH_LENGTH - extension_and_hash_size].to_string();
         }
         let mut hash = std::collections::hash_map::DefaultHasher::new();
         updated.hash(&mut hash);
-        self.options.hash(&mut hash);
+        if !self.dev {
+            self.options.hash(&mut hash);
+            // A bundle changes when any file it imports does
+            for path in self.source_files().iter().skip(1) {
+                FileSource::Local(path.clone())
+                    .last_updated()
+                    .hash(&mut hash);
+            }
+        }
         self.location.source.hash(&mut hash);
         let uuid = hash.finish();
         self.location.unique_name = format!("{file_name}{uuid:x}{extension}");
@@ -459,6 +526,13 @@ impl FileAsset {
     }
 }
 
+/// Returns the size above which variants of a processed file are written, if the file is compressible and precompression is enabled
+fn precompress_min_size(config: &Config, options: &FileOptions) -> Option<u64> {
+    let precompress = config.precompress();
+    (precompress.enabled() && !config.dev() && is_compressible(options.extension()))
+        .then(|| precompress.min_size())
+}
+
 /// A metadata asset
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
 pub struct MetadataAsset {
@@ -1,8 +1,15 @@
-use std::path::PathBuf;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::cache::config_path;
+use crate::{cache::config_path, HttpConfig, PrecompressConfig};
+
+/// The environment variable that points the assets macro to the config file of the current build
+pub const CONFIG_PATH_ENV: &str = "MANGANIS_CONFIG";
 
 fn default_assets_serve_location() -> String {
     #[cfg(target_arch = "wasm32")]
@@ -16,10 +23,20 @@ fn default_assets_serve_location() -> String {
 }
 
 /// The configuration for collecting assets
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Config {
     #[serde(default = "default_assets_serve_location")]
     assets_serve_location: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    path_prefix: Option<String>,
+    #[serde(default)]
+    http: HttpConfig,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    precompress: PrecompressConfig,
 }
 
 impl Config {
@@ -27,6 +44,7 @@ impl Config {
     pub fn with_assets_serve_location(&self, assets_serve_location: impl Into<String>) -> Self {
         Self {
             assets_serve_location: assets_serve_location.into(),
+            ..self.clone()
         }
     }
 
@@ -35,34 +53,169 @@ impl Config {
         &self.assets_serve_location
     }
 
+    /// The URL assets are served from, like `https://cdn.example.com/v3/`. If this is set, the locations of assets are absolute URLs.
+    pub fn with_base_url(&self, base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: Some(base_url.into()),
+            ..self.clone()
+        }
+    }
+
+    /// The URL assets are served from, like `https://cdn.example.com/v3/`. If this is set, the locations of assets are absolute URLs.
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// The path the application is mounted under, like `/my-app`. This is added before the assets serve location.
+    pub fn with_path_prefix(&self, path_prefix: impl Into<String>) -> Self {
+        Self {
+            path_prefix: Some(path_prefix.into()),
+            ..self.clone()
+        }
+    }
+
+    /// The path the application is mounted under, like `/my-app`. This is added before the assets serve location.
+    pub fn path_prefix(&self) -> Option<&str> {
+        self.path_prefix.as_deref()
+    }
+
+    /// The settings for downloading remote assets, like timeouts, retries and headers for private asset servers
+    pub fn with_http(&self, http: HttpConfig) -> Self {
+        Self {
+            http,
+            ..self.clone()
+        }
+    }
+
+    /// The settings for downloading remote assets, like timeouts, retries and headers for private asset servers
+    pub fn http(&self) -> &HttpConfig {
+        &self.http
+    }
+
+    /// Enables dev mode for fast local iteration. Defaults to false
+    ///
+    /// In dev mode assets are not optimized. Each asset gets a stable name that only depends on the path of its source, and the CLI links or copies the original file to the output directory. Edits to the file show up under the same location without rebuilding the application, and cargo does not rebuild the crate when the file changes. Url encoded and embedded assets are still compiled into the binary, so they are rebuilt as usual.
+    ///
+    /// The name is built from the original file name and a hash of its path, like `stylecss1a2b3c.css`. All assets share one output directory, so the hash keeps `a/style.css` and `b/style.css` apart.
+    pub fn with_dev(&self, dev: bool) -> Self {
+        Self {
+            dev,
+            ..self.clone()
+        }
+    }
+
+    /// Returns whether dev mode is enabled
+    pub fn dev(&self) -> bool {
+        self.dev
+    }
+
+    /// The settings for writing `.br` and `.gz` variants next to compressible assets, so they can be served without compressing them on every request
+    pub fn with_precompress(&self, precompress: PrecompressConfig) -> Self {
+        Self {
+            precompress,
+            ..self.clone()
+        }
+    }
+
+    /// The settings for writing `.br` and `.gz` variants next to compressible assets, so they can be served without compressing them on every request
+    pub fn precompress(&self) -> &PrecompressConfig {
+        &self.precompress
+    }
+
+    /// Returns the location an asset with the given unique name is served from
+    ///
+    /// This joins the base URL, the path prefix, the assets serve location and the name of the asset.
+    pub fn asset_location(&self, unique_name: &str) -> String {
+        let mut location = String::new();
+        let segments = [
+            self.base_url.as_deref(),
+            self.path_prefix.as_deref(),
+            Some(self.assets_serve_location.as_str()),
+        ];
+        for segment in segments.into_iter().flatten() {
+            push_segment(&mut location, segment);
+        }
+        if !location.is_empty() && !location.ends_with('/') {
+            location.push('/');
+        }
+        location.push_str(unique_name);
+        location
+    }
+
     #[doc(hidden)]
-    /// Returns the path to the config
+    /// Returns the path to the config of the current build
     /// This is only used in the macro
     pub fn config_path() -> PathBuf {
+        std::env::var_os(CONFIG_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(config_path)
+    }
+
+    /// Returns the path to the global config that is used if the build does not set [`CONFIG_PATH_ENV`]
+    pub fn global_config_path() -> PathBuf {
         config_path()
     }
 
     /// Returns the current config
+    ///
+    /// The config is read from the file in the [`CONFIG_PATH_ENV`] environment variable if it is set, and from the global config otherwise.
     pub fn current() -> Self {
-        std::fs::read(config_path())
+        if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+            if let Some(config) = Self::read(path.as_ref()) {
+                return config;
+            }
+        }
+        Self::read(&config_path()).unwrap_or_default()
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        std::fs::read(path)
             .ok()
             .and_then(|config| toml::from_str(&String::from_utf8_lossy(&config)).ok())
-            .unwrap_or_default()
     }
 
     /// Saves the config globally. This must be run before compiling the application you are collecting assets from.
     ///
     /// The assets macro will read the config from the global config file and set the assets serve location to the value in the config.
+    /// The global config is shared by every build on the machine. Prefer [`Config::save_for_build`] so builds with different configs can run at the same time.
     pub fn save(&self) {
-        let current = Self::current();
+        let current = Self::read(&config_path()).unwrap_or_default();
         if current == *self {
             return;
         }
 
+        self.save_to(config_path());
+    }
+
+    /// Saves the config to a file. Set the [`CONFIG_PATH_ENV`] environment variable to the path when compiling the application to use it.
+    pub fn save_to(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if Self::read(path).as_ref() == Some(self) {
+            // Don't touch the file so the macro is not rebuilt
+            return;
+        }
+
         let config = toml::to_string(&self).unwrap();
-        let config_path = config_path();
-        std::fs::create_dir_all(config_path.parent().unwrap()).unwrap();
-        std::fs::write(config_path, config).unwrap();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(path, config).unwrap();
+    }
+
+    /// Saves the config for a single build in the target directory and returns the path to it.
+    ///
+    /// Set the [`CONFIG_PATH_ENV`] environment variable to the returned path when compiling the application. Each distinct config is saved to its own file, so builds with different configs can share a target directory and run at the same time.
+    pub fn save_for_build(&self, target_dir: impl AsRef<Path>) -> PathBuf {
+        let config = toml::to_string(&self).unwrap();
+        let mut hasher = DefaultHasher::new();
+        config.hash(&mut hasher);
+
+        let path = target_dir
+            .as_ref()
+            .join("manganis")
+            .join(format!("config-{:016x}.toml", hasher.finish()));
+        self.save_to(&path);
+        path
     }
 }
 
@@ -70,6 +223,28 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             assets_serve_location: default_assets_serve_location(),
+            base_url: None,
+            path_prefix: None,
+            http: HttpConfig::default(),
+            dev: false,
+            precompress: PrecompressConfig::default(),
         }
     }
 }
+
+/// Appends a segment of a path or URL, making sure there is exactly one slash between segments
+fn push_segment(location: &mut String, segment: &str) {
+    if location.is_empty() {
+        location.push_str(segment);
+        return;
+    }
+
+    let segment = segment.trim_start_matches("./").trim_start_matches('/');
+    if segment.is_empty() {
+        return;
+    }
+    if !location.ends_with('/') {
+        location.push('/');
+    }
+    location.push_str(segment);
+}
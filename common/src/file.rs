@@ -12,6 +12,8 @@ pub enum FileOptions {
     Font(FontOptions),
     /// A css asset
     Css(CssOptions),
+    /// A javascript asset
+    Js(JsOptions),
     /// Any other asset
     Other(UnknownFileOptions),
 }
@@ -23,6 +25,7 @@ impl Display for FileOptions {
             Self::Video(options) => write!(f, "{}", options),
             Self::Font(options) => write!(f, "{}", options),
             Self::Css(options) => write!(f, "{}", options),
+            Self::Js(options) => write!(f, "{}", options),
             Self::Other(options) => write!(f, "{}", options),
         }
     }
@@ -43,6 +46,7 @@ impl FileOptions {
             Some("woff") => Self::Font(FontOptions::new(FontType::WOFF)),
             Some("woff2") => Self::Font(FontOptions::new(FontType::WOFF2)),
             Some("css") => Self::Css(CssOptions::default()),
+            Some("js") | Some("mjs") => Self::Js(JsOptions::default()),
             _ => Self::Other(UnknownFileOptions {
                 extension: extension.map(String::from),
             }),
@@ -69,6 +73,7 @@ impl FileOptions {
                 FontType::WOFF2 => Some("woff2"),
             },
             Self::Css(_) => Some("css"),
+            Self::Js(_) => Some("js"),
             Self::Other(extension) => extension.extension.as_deref(),
         }
     }
@@ -87,6 +92,8 @@ pub struct ImageOptions {
     size: Option<(u32, u32)>,
     preload: bool,
     ty: ImageType,
+    #[serde(default)]
+    quality: Option<u8>,
 }
 
 impl Display for ImageOptions {
@@ -96,6 +103,9 @@ impl Display for ImageOptions {
         } else {
             write!(f, "{}", self.ty)?;
         }
+        if let Some(quality) = self.quality {
+            write!(f, " (quality {})", quality)?;
+        }
         if self.compress {
             write!(f, " (compressed)")?;
         }
@@ -114,6 +124,7 @@ impl ImageOptions {
             size,
             ty,
             preload: false,
+            quality: None,
         }
     }
 
@@ -156,6 +167,16 @@ impl ImageOptions {
     pub fn set_compress(&mut self, compress: bool) {
         self.compress = compress;
     }
+
+    /// Returns the quality (0-100) lossy formats are encoded with, if it is set
+    pub fn quality(&self) -> Option<u8> {
+        self.quality
+    }
+
+    /// Sets the quality (0-100) lossy formats are encoded with. Webp images are always encoded losslessly
+    pub fn set_quality(&mut self, quality: Option<u8>) {
+        self.quality = quality;
+    }
 }
 
 /// The type of an image
@@ -341,6 +362,80 @@ impl Default for CssOptions {
     }
 }
 
+/// The options for a javascript asset
+///
+/// Files are parsed as ES modules if they contain `import` or `export` statements, and as scripts otherwise.
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Hash)]
+pub struct JsOptions {
+    minify: bool,
+    #[serde(default)]
+    bundle: bool,
+    #[serde(default)]
+    source_map: bool,
+}
+
+impl Display for JsOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags = [
+            (self.minify, "minified"),
+            (self.bundle, "bundled"),
+            (self.source_map, "source map"),
+        ];
+        let flags: Vec<_> = flags
+            .into_iter()
+            .filter_map(|(enabled, flag)| enabled.then_some(flag))
+            .collect();
+        write!(f, "{}", flags.join(", "))
+    }
+}
+
+impl JsOptions {
+    /// Creates a new js options struct
+    pub fn new(minify: bool) -> Self {
+        Self {
+            minify,
+            bundle: false,
+            source_map: false,
+        }
+    }
+
+    /// Returns whether the javascript should be minified
+    pub fn minify(&self) -> bool {
+        self.minify
+    }
+
+    /// Sets whether the javascript should be minified
+    pub fn set_minify(&mut self, minify: bool) {
+        self.minify = minify;
+    }
+
+    /// Returns whether relative imports are bundled into the file
+    pub fn bundle(&self) -> bool {
+        self.bundle
+    }
+
+    /// Sets whether relative imports like `./utils.js` are bundled into the file. Other imports are kept
+    pub fn set_bundle(&mut self, bundle: bool) {
+        self.bundle = bundle;
+    }
+
+    /// Returns whether a source map is written next to the processed file
+    pub fn source_map(&self) -> bool {
+        self.source_map
+    }
+
+    /// Sets whether a source map is written next to the processed file, as `<unique name>.map`
+    pub fn set_source_map(&mut self, source_map: bool) {
+        self.source_map = source_map;
+    }
+}
+
+impl Default for JsOptions {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
 /// The options for an unknown file asset
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Hash)]
 pub struct UnknownFileOptions {
@@ -0,0 +1,294 @@
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::served_location, AssetError, Config, FileAsset, FileOptions, FileSource, PackageConfig,
+    UnknownFileOptions,
+};
+
+/// A set of files that are collected together, from a directory or from the files that match a glob pattern
+///
+/// The files keep their structure relative to the directory under a folder with a unique name, so `icons/arrow.svg` in `static/` is served from `<serve location>/static1234/icons/arrow.svg`.
+///
+/// Hidden files and directories, like `.DS_Store` or `.gitkeep`, are skipped. Symlinked directories are not followed, so a symlink that points to one of its parents cannot make the walk loop forever.
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+pub struct FolderAsset {
+    unique_name: String,
+    source: PathBuf,
+    #[serde(default)]
+    pattern: Option<String>,
+    files: Vec<FolderFile>,
+    #[serde(default)]
+    dev: bool,
+    #[serde(default)]
+    serve_location: Option<String>,
+}
+
+/// A file in a [`FolderAsset`]
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+pub struct FolderFile {
+    relative_path: String,
+    asset: FileAsset,
+}
+
+impl FolderFile {
+    /// Returns the path of the original file relative to the folder, with `/` separators
+    pub fn relative_path(&self) -> &str {
+        &self.relative_path
+    }
+
+    /// Returns the file asset. Its unique name is the path of the processed file relative to the folder
+    pub fn asset(&self) -> &FileAsset {
+        &self.asset
+    }
+}
+
+impl FolderAsset {
+    /// Collects every file in a directory and its subdirectories. Relative paths are resolved from the given directory
+    ///
+    /// The defaults of the package config are applied to the options of each file, see [`PackageConfig::apply_defaults`].
+    pub fn from_dir(
+        dir: &str,
+        base_dir: impl Into<PathBuf>,
+        package_config: &PackageConfig,
+    ) -> Result<Self, AssetError> {
+        let base_dir = base_dir.into();
+        let path = base_dir.join(dir);
+        let source = match path.canonicalize() {
+            Ok(path) if path.is_dir() => path,
+            Ok(path) => return Err(AssetError::NotDirectory(path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && Path::new(dir).is_absolute() => {
+                return Err(AssetError::NotFoundAbsolute(dir.into()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AssetError::NotFoundRelative(base_dir, dir.into()))
+            }
+            Err(e) => return Err(AssetError::IO(path, e)),
+        };
+
+        let mut paths = Vec::new();
+        walk_dir(&source, &mut paths).map_err(|e| AssetError::IO(source.clone(), e))?;
+        Ok(Self::from_paths(source, None, paths, package_config))
+    }
+
+    /// Collects every file that matches a glob pattern like `icons/**/*.svg`. Relative patterns are resolved from the given directory
+    ///
+    /// The structure of the files is kept relative to the part of the pattern before the first wildcard, `icons/` in the example. The defaults of the package config are applied to the options of each file, see [`PackageConfig::apply_defaults`].
+    pub fn from_glob(
+        pattern: &str,
+        base_dir: impl Into<PathBuf>,
+        package_config: &PackageConfig,
+    ) -> Result<Self, AssetError> {
+        let base_dir = base_dir.into();
+        let invalid = |error: String| AssetError::InvalidGlob(pattern.to_string(), error);
+
+        // The directory the structure is kept relative to is the part of the pattern without wildcards
+        let literal: PathBuf = Path::new(pattern)
+            .components()
+            .take_while(|component| {
+                !component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '['])
+            })
+            .collect();
+        if literal == Path::new(pattern) {
+            return Err(invalid(
+                "the pattern does not contain a wildcard. Use dir() or file() instead".into(),
+            ));
+        }
+        let source = base_dir.join(&literal).canonicalize().map_err(|_| {
+            AssetError::NotFoundRelative(base_dir.clone(), literal.display().to_string())
+        })?;
+
+        let rest = Path::new(pattern)
+            .strip_prefix(&literal)
+            .unwrap_or(Path::new(pattern));
+        let full_pattern = format!(
+            "{}/{}",
+            glob::Pattern::escape(&source.to_string_lossy()),
+            rest.to_string_lossy()
+        );
+        let matcher = glob::Pattern::new(&full_pattern).map_err(|e| invalid(e.to_string()))?;
+        // Match the files of the directory instead of letting glob walk it, so hidden files and symlinked directories are skipped the same way as in `from_dir`
+        let mut paths = Vec::new();
+        walk_dir(&source, &mut paths).map_err(|e| AssetError::IO(source.clone(), e))?;
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        paths.retain(|path| matcher.matches_path_with(path, options));
+        if paths.is_empty() {
+            return Err(invalid("the pattern did not match any files".into()));
+        }
+
+        Ok(Self::from_paths(
+            source,
+            Some(pattern.to_string()),
+            paths,
+            package_config,
+        ))
+    }
+
+    fn from_paths(
+        source: PathBuf,
+        pattern: Option<String>,
+        paths: Vec<PathBuf>,
+        package_config: &PackageConfig,
+    ) -> Self {
+        let mut files: Vec<FolderFile> = paths
+            .into_iter()
+            .map(|path| {
+                let relative_path = path
+                    .strip_prefix(&source)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let mut asset = FileAsset::new(FileSource::Local(path));
+                // Videos and fonts are not processed yet, so they are copied as they are
+                if matches!(
+                    asset.options(),
+                    FileOptions::Video(_) | FileOptions::Font(_)
+                ) {
+                    let extension = asset.options().extension().map(String::from);
+                    asset =
+                        asset.with_options(FileOptions::Other(UnknownFileOptions::new(extension)));
+                }
+                asset.with_options_mut(|options| package_config.apply_defaults(options));
+                // The processed file keeps its relative path, with the extension of the processed format
+                let processed_path = match asset.options().extension() {
+                    Some(extension) if !asset.dev() => Path::new(&relative_path)
+                        .with_extension(extension)
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                    Some(_) | None => relative_path.clone(),
+                };
+                FolderFile {
+                    relative_path,
+                    asset: asset.with_unique_name(processed_path),
+                }
+            })
+            .collect();
+        // The runtime handle looks files up with a binary search over the relative paths
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        let config = Config::current();
+        let mut myself = Self {
+            unique_name: String::new(),
+            source,
+            pattern,
+            files,
+            dev: config.dev(),
+            serve_location: Some(config.asset_location("")),
+        };
+        myself.regenerate_unique_name();
+        myself
+    }
+
+    /// Returns the name of the folder the files are collected into
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Returns the directory the relative paths of the files start from
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Returns the glob pattern the files were matched with, if the folder was built from a pattern
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Returns the files in the folder, sorted by their relative path
+    pub fn files(&self) -> &[FolderFile] {
+        &self.files
+    }
+
+    /// Returns the file with the given path relative to the folder
+    pub fn get(&self, relative_path: &str) -> Option<&FolderFile> {
+        self.files
+            .iter()
+            .find(|file| file.relative_path == relative_path)
+    }
+
+    /// Returns whether the folder was collected in dev mode. See [`Config::with_dev`]
+    pub fn dev(&self) -> bool {
+        self.dev
+    }
+
+    /// Returns the location the folder is served from, in the config of the build that collected it
+    pub fn served_location(&self) -> String {
+        served_location(self.serve_location.as_deref(), &self.unique_name)
+    }
+
+    /// Returns the location a file in the folder is served from, in the config of the build that collected it
+    pub fn served_file_location(&self, file: &FolderFile) -> String {
+        served_location(self.serve_location.as_deref(), &self.file_unique_name(file))
+    }
+
+    /// Returns the path of the processed file relative to the output directory, like `static1234/icons/arrow.svg`
+    pub fn file_unique_name(&self, file: &FolderFile) -> String {
+        format!(
+            "{}/{}",
+            self.unique_name,
+            file.asset.location().unique_name()
+        )
+    }
+
+    /// Regenerates the name of the folder. The name depends on when each file was last updated, so call this after a file changed to get the name the macro would generate now.
+    ///
+    /// The files keep their relative paths. In dev mode the name only depends on the source directory and the pattern, so it does not change.
+    pub fn regenerate_unique_name(&mut self) {
+        let mut name = self
+            .source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        name.retain(|c| c.is_alphanumeric());
+        name.truncate(64);
+
+        let mut hash = std::collections::hash_map::DefaultHasher::new();
+        self.source.hash(&mut hash);
+        self.pattern.hash(&mut hash);
+        // Dev folders keep their name when the files change
+        if !self.dev {
+            for file in &self.files {
+                file.relative_path.hash(&mut hash);
+                file.asset.location().unique_name().hash(&mut hash);
+                file.asset
+                    .location()
+                    .source()
+                    .last_updated()
+                    .hash(&mut hash);
+                file.asset.options().hash(&mut hash);
+            }
+        }
+        self.unique_name = format!("{name}{:x}", hash.finish());
+    }
+}
+
+/// Collects the files in a directory and its subdirectories. Hidden entries and symlinked directories are skipped
+fn walk_dir(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        // The file type of the entry does not follow symlinks, so a symlinked directory is never walked
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk_dir(&path, paths)?;
+        } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use anyhow::Context;
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+fn default_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    250
+}
+
+/// The settings for downloading remote assets
+///
+/// ```toml
+/// [http]
+/// timeout_ms = 30000
+/// retries = 3
+/// retry_backoff_ms = 250
+/// proxy = "http://proxy.internal:3128"
+///
+/// # Headers sent to a host. `${NAME}` is replaced with the environment variable NAME when the request is sent
+/// [http.headers."assets.example.com"]
+/// Authorization = "Bearer ${ASSETS_TOKEN}"
+/// ```
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct HttpConfig {
+    #[serde(default = "default_timeout_ms")]
+    timeout_ms: u64,
+    #[serde(default = "default_retries")]
+    retries: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    retry_backoff_ms: u64,
+    #[serde(default)]
+    proxy: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout_ms(),
+            retries: default_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            proxy: None,
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Sets how long a single request may take before it is cancelled. Defaults to 30 seconds
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout_ms: timeout.as_millis() as u64,
+            ..self.clone()
+        }
+    }
+
+    /// Returns how long a single request may take before it is cancelled
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// Sets how many times a request is retried after a connection error, a timeout or a 5xx or 429 response. Defaults to 3
+    pub fn with_retries(&self, retries: u32) -> Self {
+        Self {
+            retries,
+            ..self.clone()
+        }
+    }
+
+    /// Returns how many times a failed request is retried
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Sets the delay before the first retry. The delay doubles after every retry. Defaults to 250 milliseconds
+    pub fn with_retry_backoff(&self, backoff: Duration) -> Self {
+        Self {
+            retry_backoff_ms: backoff.as_millis() as u64,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the delay before the first retry
+    pub fn retry_backoff(&self) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms)
+    }
+
+    /// Sets the proxy every request is sent through. If this is not set, the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables are used
+    pub fn with_proxy(&self, proxy: impl Into<String>) -> Self {
+        Self {
+            proxy: Some(proxy.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the proxy every request is sent through
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// Adds a header that is sent with every request to a host, like an auth token for a private asset server
+    ///
+    /// The host may include a port, like `localhost:8080`. `${NAME}` in the value is replaced with the environment variable `NAME` when the request is sent, so secrets don't have to be written to the config file.
+    pub fn with_header(
+        &self,
+        host: impl Into<String>,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let mut config = self.clone();
+        config
+            .headers
+            .entry(host.into())
+            .or_default()
+            .insert(name.into(), value.into());
+        config
+    }
+
+    /// Returns the headers that are sent to each host
+    pub fn headers(&self) -> &BTreeMap<String, BTreeMap<String, String>> {
+        &self.headers
+    }
+}
+
+/// Downloads remote assets with the settings in a [`HttpConfig`]
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    client: Client,
+    config: HttpConfig,
+}
+
+/// A file downloaded by a [`Fetcher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedFile {
+    /// The content of the file
+    pub bytes: Vec<u8>,
+    /// The `Content-Type` header of the response
+    pub content_type: Option<String>,
+    /// The `Last-Modified` header of the response
+    pub last_modified: Option<String>,
+}
+
+impl Fetcher {
+    /// Creates a fetcher with the given settings
+    pub fn new(config: HttpConfig) -> anyhow::Result<Self> {
+        let mut builder = Client::builder()
+            .timeout(config.timeout())
+            .user_agent(concat!("manganis/", env!("CARGO_PKG_VERSION")));
+        if let Some(proxy) = config.proxy() {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy `{proxy}`"))?,
+            );
+        }
+        let client = builder
+            .build()
+            .context("Failed to create the HTTP client")?;
+        Ok(Self { client, config })
+    }
+
+    /// Returns a fetcher with the settings in the [`crate::Config`] of the current build
+    ///
+    /// The fetcher is shared by the whole process, so connections are reused between downloads. It is only created again if the settings change.
+    pub fn current() -> anyhow::Result<Self> {
+        static CURRENT: OnceLock<Mutex<Option<Fetcher>>> = OnceLock::new();
+        let config = crate::Config::current().http().clone();
+        let mut current = CURRENT.get_or_init(Default::default).lock().unwrap();
+        match &*current {
+            Some(fetcher) if fetcher.config == config => Ok(fetcher.clone()),
+            _ => {
+                let fetcher = Self::new(config)?;
+                *current = Some(fetcher.clone());
+                Ok(fetcher)
+            }
+        }
+    }
+
+    /// Downloads a file. Fails if the server does not respond with a 2xx status
+    ///
+    /// Connection errors, timeouts and 5xx or 429 responses are retried with an exponential backoff.
+    pub fn get(&self, url: &Url) -> anyhow::Result<FetchedFile> {
+        let headers = self.headers_for(url)?;
+        let mut backoff = self.config.retry_backoff();
+        let mut attempt = 0;
+        loop {
+            match self.try_get(url, &headers) {
+                Ok(file) => return Ok(file),
+                Err(FetchError::Retryable(err)) if attempt < self.config.retries() => {
+                    attempt += 1;
+                    tracing::warn!(
+                        "Failed to download {url}: {err:#}. Retrying in {backoff:?} ({attempt}/{})",
+                        self.config.retries()
+                    );
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                Err(FetchError::Retryable(err) | FetchError::Fatal(err)) => {
+                    return Err(err.context(format!("Failed to download asset from url: {url}")))
+                }
+            }
+        }
+    }
+
+    fn try_get(&self, url: &Url, headers: &HeaderMap) -> Result<FetchedFile, FetchError> {
+        let response = self
+            .client
+            .get(url.as_str())
+            .headers(headers.clone())
+            .send()
+            .map_err(|err| FetchError::Retryable(err.into()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let err = anyhow::anyhow!("the server responded with {status}");
+            return Err(
+                if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                    FetchError::Retryable(err)
+                } else {
+                    FetchError::Fatal(err)
+                },
+            );
+        }
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let content_type = header("content-type");
+        let last_modified = header("last-modified");
+        let bytes = response
+            .bytes()
+            .map_err(|err| FetchError::Retryable(err.into()))?
+            .to_vec();
+
+        Ok(FetchedFile {
+            bytes,
+            content_type,
+            last_modified,
+        })
+    }
+
+    /// Returns the configured headers for the host of the url
+    fn headers_for(&self, url: &Url) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        let Some(host) = url.host_str() else {
+            return Ok(headers);
+        };
+        let with_port = url.port().map(|port| format!("{host}:{port}"));
+        let hosts = std::iter::once(host).chain(with_port.as_deref());
+
+        for host in hosts {
+            for (name, value) in self.config.headers.get(host).into_iter().flatten() {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .with_context(|| format!("Invalid header name `{name}` for {host}"))?;
+                let value = HeaderValue::from_str(&expand_env(value)?)
+                    .with_context(|| format!("Invalid value for header `{name}` for {host}"))?;
+                headers.insert(name, value);
+            }
+        }
+        Ok(headers)
+    }
+}
+
+enum FetchError {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+/// Replaces `${NAME}` with the value of the environment variable `NAME`
+fn expand_env(value: &str) -> anyhow::Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        let var = std::env::var(name)
+            .with_context(|| format!("The environment variable {name} is not set"))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + len + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
@@ -0,0 +1,146 @@
+use std::{fmt::Display, str::FromStr};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// The hash algorithm of an [`Integrity`]
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Copy, Hash, Eq)]
+pub enum IntegrityAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-384
+    Sha384,
+    /// SHA-512
+    Sha512,
+}
+
+impl IntegrityAlgorithm {
+    fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => Sha256::digest(bytes).to_vec(),
+            Self::Sha384 => Sha384::digest(bytes).to_vec(),
+            Self::Sha512 => Sha512::digest(bytes).to_vec(),
+        }
+    }
+}
+
+impl Display for IntegrityAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sha256 => write!(f, "sha256"),
+            Self::Sha384 => write!(f, "sha384"),
+            Self::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+/// The expected hash of the content of an asset, written like a [subresource integrity](https://developer.mozilla.org/en-US/docs/Web/Security/Subresource_Integrity) value: `sha256-<base64 digest>`
+#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Hash, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Integrity {
+    algorithm: IntegrityAlgorithm,
+    digest: Vec<u8>,
+}
+
+impl Integrity {
+    /// Computes the integrity of some content
+    pub fn of(algorithm: IntegrityAlgorithm, bytes: &[u8]) -> Self {
+        Self {
+            algorithm,
+            digest: algorithm.digest(bytes),
+        }
+    }
+
+    /// Returns the hash algorithm
+    pub fn algorithm(&self) -> IntegrityAlgorithm {
+        self.algorithm
+    }
+
+    /// Checks that the content matches the expected hash
+    pub fn verify(&self, bytes: &[u8]) -> Result<(), IntegrityError> {
+        let found = Self::of(self.algorithm, bytes);
+        if found == *self {
+            Ok(())
+        } else {
+            Err(IntegrityError::Mismatch {
+                expected: self.clone(),
+                found,
+            })
+        }
+    }
+}
+
+impl Display for Integrity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digest = base64::engine::general_purpose::STANDARD.encode(&self.digest);
+        write!(f, "{}-{}", self.algorithm, digest)
+    }
+}
+
+impl FromStr for Integrity {
+    type Err = IntegrityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || IntegrityError::Invalid(s.to_string());
+        let (algorithm, digest) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let algorithm = match algorithm {
+            "sha256" => IntegrityAlgorithm::Sha256,
+            "sha384" => IntegrityAlgorithm::Sha384,
+            "sha512" => IntegrityAlgorithm::Sha512,
+            _ => return Err(invalid()),
+        };
+        let digest = base64::engine::general_purpose::STANDARD
+            .decode(digest)
+            .map_err(|_| invalid())?;
+        if digest.len() != algorithm.digest(&[]).len() {
+            return Err(invalid());
+        }
+        Ok(Self { algorithm, digest })
+    }
+}
+
+impl TryFrom<String> for Integrity {
+    type Error = IntegrityError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Integrity> for String {
+    fn from(value: Integrity) -> Self {
+        value.to_string()
+    }
+}
+
+/// An error while parsing or checking an [`Integrity`]
+#[derive(Debug)]
+pub enum IntegrityError {
+    /// The integrity is not written as `sha256-<base64 digest>`, `sha384-...` or `sha512-...`
+    Invalid(String),
+    /// The content does not match the expected hash
+    Mismatch {
+        /// The integrity the asset was declared with
+        expected: Integrity,
+        /// The integrity of the content that was read
+        found: Integrity,
+    },
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::Invalid(integrity) => write!(
+                f,
+                "invalid integrity `{integrity}`. Expected a base64 encoded digest like `sha256-<digest>`, `sha384-<digest>` or `sha512-<digest>`"
+            ),
+            IntegrityError::Mismatch { expected, found } => write!(
+                f,
+                "integrity check failed: expected `{expected}`, but the content hashes to `{found}`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+
+/// Returns the local files a bundle of `entry` is built from: the entry and every file it imports with a relative path, directly or through other files
+///
+/// Files that cannot be read and imports that do not resolve to a file are skipped. See [`ModuleGraph`]
+pub fn bundled_files(entry: &Path) -> Vec<PathBuf> {
+    let entry = entry.canonicalize().unwrap_or_else(|_| entry.to_path_buf());
+    let graph = ModuleGraph::new(&entry, |path| {
+        Ok::<_, std::convert::Infallible>(std::fs::read_to_string(path).unwrap_or_default())
+    });
+    match graph {
+        Ok(graph) => graph.files,
+        Err(never) => match never {},
+    }
+}
+
+/// The module graph of a javascript entry, shared by the macro and the bundler so both see the same files
+///
+/// This only looks at the `import` and `export ... from` statements and the `import(..)` calls of the files, so it can run in the macro without a javascript parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleGraph {
+    files: Vec<PathBuf>,
+    external_imports: Vec<String>,
+    dynamic_imports: Vec<(PathBuf, String)>,
+    unresolved_imports: Vec<(PathBuf, String)>,
+}
+
+impl ModuleGraph {
+    /// Follows the relative imports of `entry`. `read` returns the source of each file in the graph
+    pub fn new<E>(
+        entry: &Path,
+        mut read: impl FnMut(&Path) -> Result<String, E>,
+    ) -> Result<Self, E> {
+        let mut graph = Self {
+            files: vec![entry.to_path_buf()],
+            external_imports: Vec::new(),
+            dynamic_imports: Vec::new(),
+            unresolved_imports: Vec::new(),
+        };
+        let mut index = 0;
+        while index < graph.files.len() {
+            let importer = graph.files[index].clone();
+            for import in imports(&read(&importer)?) {
+                if !is_relative_import(&import.specifier) {
+                    if !import.dynamic && !graph.external_imports.contains(&import.specifier) {
+                        graph.external_imports.push(import.specifier);
+                    }
+                    continue;
+                }
+                let Some(path) = resolve_relative_import(&importer, &import.specifier) else {
+                    graph
+                        .unresolved_imports
+                        .push((importer.clone(), import.specifier));
+                    continue;
+                };
+                if import.dynamic {
+                    graph
+                        .dynamic_imports
+                        .push((importer.clone(), import.specifier));
+                }
+                if !graph.files.contains(&path) {
+                    graph.files.push(path);
+                }
+            }
+            index += 1;
+        }
+        Ok(graph)
+    }
+
+    /// Returns the entry and every file it imports with a relative path, directly or through other files
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns the static imports that are not relative, like `preact` or `https://esm.sh/preact`. These are left for the browser to load
+    pub fn external_imports(&self) -> &[String] {
+        &self.external_imports
+    }
+
+    /// Returns the files with an `import(..)` of a relative path, with the path they import
+    pub fn dynamic_imports(&self) -> &[(PathBuf, String)] {
+        &self.dynamic_imports
+    }
+
+    /// Returns the files with a relative import that does not resolve to a file, with the path they import
+    pub fn unresolved_imports(&self) -> &[(PathBuf, String)] {
+        &self.unresolved_imports
+    }
+}
+
+/// Resolves a relative import like `./utils` from the file `importer` to `utils.js` next to it. Returns `None` if the import is not relative or no file matches
+///
+/// The path is tried as it is, with a `.js` or `.mjs` extension, and as a directory with an `index.js` or `index.mjs` file.
+pub fn resolve_relative_import(importer: &Path, specifier: &str) -> Option<PathBuf> {
+    if !is_relative_import(specifier) {
+        return None;
+    }
+    let path = importer.parent().unwrap_or(Path::new("")).join(specifier);
+    let with_extension = |extension: &str| {
+        let mut path = path.clone().into_os_string();
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    [
+        path.clone(),
+        with_extension(".js"),
+        with_extension(".mjs"),
+        path.join("index.js"),
+        path.join("index.mjs"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .and_then(|candidate| candidate.canonicalize().ok())
+}
+
+/// Returns whether an import specifier is a path like `./utils.js`, rather than a package or a URL
+pub fn is_relative_import(specifier: &str) -> bool {
+    specifier.starts_with("./") || specifier.starts_with("../") || specifier.starts_with('/')
+}
+
+/// An import in a javascript file
+struct Import {
+    specifier: String,
+    /// Whether the module is loaded with `import(..)` when the code runs
+    dynamic: bool,
+}
+
+/// Returns the imports in a file: the strings after `import`, after `from` and in `import(..)`
+fn imports(source: &str) -> Vec<Import> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut imports = Vec::new();
+    // The last word outside of strings and comments, and whether it followed a `.` like in `Array.from`
+    let mut last_word = String::new();
+    let mut after_dot = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '"' | '\'' | '`' => {
+                let start = i + 1;
+                i += 1;
+                // Plain strings end at the end of the line, so a stray quote does not swallow the rest of the file
+                while i < chars.len() && chars[i] != c && (c == '`' || chars[i] != '\n') {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if c != '`'
+                    && !after_dot
+                    && matches!(last_word.as_str(), "import" | "from" | "import(")
+                {
+                    imports.push(Import {
+                        specifier: chars[start..i.min(chars.len())].iter().collect(),
+                        dynamic: last_word == "import(",
+                    });
+                }
+                last_word.clear();
+                i += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$'))
+                {
+                    i += 1;
+                }
+                after_dot = chars[..start]
+                    .iter()
+                    .rev()
+                    .find(|c| !c.is_whitespace())
+                    .is_some_and(|c| *c == '.');
+                last_word = chars[start..i].iter().collect();
+            }
+            c if c.is_whitespace() => i += 1,
+            // A dynamic import, the string inside the parentheses is the specifier
+            '(' if last_word == "import" => {
+                last_word.push('(');
+                i += 1;
+            }
+            _ => {
+                last_word.clear();
+                i += 1;
+            }
+        }
+    }
+    imports
+}
@@ -5,10 +5,25 @@ mod asset;
 pub mod cache;
 mod config;
 mod file;
+mod folder;
+mod http;
+mod integrity;
+mod js;
 pub mod linker;
 mod manifest;
+mod package_config;
+mod precompress;
+pub mod section;
+mod vendor;
 
 pub use asset::*;
 pub use config::*;
 pub use file::*;
+pub use folder::*;
+pub use http::*;
+pub use integrity::*;
+pub use js::*;
 pub use manifest::*;
+pub use package_config::*;
+pub use precompress::*;
+pub use vendor::*;
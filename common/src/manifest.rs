@@ -1,15 +1,40 @@
-use crate::AssetType;
+use std::collections::BTreeMap;
+
+use crate::{section::SectionRecord, AssetType, ContentEncoding};
 
 /// A manifest of all assets collected from dependencies
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct AssetManifest {
     pub(crate) assets: Vec<AssetType>,
+    // The crate that declared each asset, if it is known
+    pub(crate) packages: Vec<Option<String>>,
+    // The precompressed variants of each processed file, by its path relative to the output directory
+    pub(crate) precompressed: BTreeMap<String, Vec<ContentEncoding>>,
 }
 
 impl AssetManifest {
     /// Creates a new asset manifest
     pub fn new(assets: Vec<AssetType>) -> Self {
-        Self { assets }
+        let packages = vec![None; assets.len()];
+        Self {
+            assets,
+            packages,
+            precompressed: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a new asset manifest from the records decoded from the link section
+    pub fn from_records(records: Vec<SectionRecord>) -> Self {
+        let packages = records
+            .iter()
+            .map(|record| Some(record.package().to_string()))
+            .collect();
+        let assets = records.into_iter().map(SectionRecord::into_asset).collect();
+        Self {
+            assets,
+            packages,
+            precompressed: BTreeMap::new(),
+        }
     }
 
     /// Returns all assets collected from dependencies
@@ -17,6 +42,47 @@ impl AssetManifest {
         &self.assets
     }
 
+    /// Returns all assets mutably, for example to update their unique names after a source changed
+    pub fn assets_mut(&mut self) -> &mut [AssetType] {
+        &mut self.assets
+    }
+
+    /// Returns all assets with the name of the crate that declared them, if it is known
+    pub fn assets_with_packages(&self) -> impl Iterator<Item = (&AssetType, Option<&str>)> {
+        self.assets
+            .iter()
+            .zip(self.packages.iter().map(Option::as_deref))
+    }
+
+    /// Returns the precompressed variants that were written next to a processed file, by its path relative to the output directory
+    pub fn precompressed(&self, unique_name: &str) -> &[ContentEncoding] {
+        self.precompressed
+            .get(unique_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Records the precompressed variants that were written next to a processed file. An empty list removes the record
+    pub fn set_precompressed(
+        &mut self,
+        unique_name: impl Into<String>,
+        encodings: Vec<ContentEncoding>,
+    ) {
+        let unique_name = unique_name.into();
+        if encodings.is_empty() {
+            self.precompressed.remove(&unique_name);
+        } else {
+            self.precompressed.insert(unique_name, encodings);
+        }
+    }
+
+    /// Returns every processed file with precompressed variants and its variants
+    pub fn precompressed_files(&self) -> impl Iterator<Item = (&str, &[ContentEncoding])> {
+        self.precompressed
+            .iter()
+            .map(|(name, encodings)| (name.as_str(), encodings.as_slice()))
+    }
+
     #[cfg(feature = "html")]
     /// Returns the HTML that should be injected into the head of the page
     pub fn head(&self) -> String {
@@ -30,13 +96,11 @@ impl AssetManifest {
                             "<link rel=\"stylesheet\" href=\"{asset_path}\">\n"
                         ))
                     }
-                    crate::FileOptions::Image(image_options) => {
-                        if image_options.preload() {
-                            let asset_path = file.served_location();
-                            head.push_str(&format!(
-                                "<link rel=\"preload\" as=\"image\" href=\"{asset_path}\">\n"
-                            ))
-                        }
+                    crate::FileOptions::Image(image_options) if image_options.preload() => {
+                        let asset_path = file.served_location();
+                        head.push_str(&format!(
+                            "<link rel=\"preload\" as=\"image\" href=\"{asset_path}\">\n"
+                        ))
                     }
                     _ => {}
                 }
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{cache::manifest_dir, CssOptions, FileOptions, ImageOptions, ImageType};
+
+/// The defaults a crate sets for its own assets in the `[package.metadata.manganis]` section of its Cargo.toml
+///
+/// ```toml
+/// [package.metadata.manganis]
+/// # The format images are converted to if the `mg!` call does not set one
+/// image-format = "webp"
+/// # The quality (0-100) lossy images are encoded with
+/// image-quality = 80
+/// # Whether css files are minified
+/// minify-css = true
+/// # Whether javascript files are minified
+/// minify-js = true
+/// # The directory relative asset paths are resolved from
+/// base-dir = "assets"
+/// ```
+///
+/// Unknown keys are ignored with a warning, so a typo does not break every asset in the crate.
+#[derive(Deserialize, Debug, Default, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageConfig {
+    #[serde(default, deserialize_with = "deserialize_image_format")]
+    image_format: Option<ImageType>,
+    #[serde(default)]
+    image_quality: Option<u8>,
+    #[serde(default)]
+    minify_css: Option<bool>,
+    #[serde(default)]
+    minify_js: Option<bool>,
+    #[serde(default)]
+    base_dir: Option<PathBuf>,
+}
+
+fn deserialize_image_format<'de, D>(deserializer: D) -> Result<Option<ImageType>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let format = String::deserialize(deserializer)?;
+    format.to_lowercase().parse().map(Some).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "unknown image format `{format}`. Supported formats are png, jpg, webp, avif"
+        ))
+    })
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<ManifestPackage>,
+}
+
+#[derive(Deserialize)]
+struct ManifestPackage {
+    metadata: Option<ManifestMetadata>,
+}
+
+#[derive(Deserialize)]
+struct ManifestMetadata {
+    manganis: Option<toml::Table>,
+}
+
+/// The keys of the `[package.metadata.manganis]` section
+const KNOWN_KEYS: &[&str] = &[
+    "image-format",
+    "image-quality",
+    "minify-css",
+    "minify-js",
+    "base-dir",
+];
+
+impl PackageConfig {
+    /// Reads the config of the crate that is currently being compiled
+    ///
+    /// This is only used in the macro
+    pub fn current() -> anyhow::Result<Self> {
+        Self::from_manifest(manifest_dir().join("Cargo.toml"))
+    }
+
+    /// Reads the config from the `[package.metadata.manganis]` section of a Cargo.toml. Returns the default config if the section is missing
+    pub fn from_manifest(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let manifest = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest: Manifest = toml::from_str(&manifest)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let mut table = manifest
+            .package
+            .and_then(|package| package.metadata)
+            .and_then(|metadata| metadata.manganis)
+            .unwrap_or_default();
+        table.retain(|key, _| {
+            let known = KNOWN_KEYS.contains(&key);
+            if !known {
+                tracing::warn!(
+                    "Ignoring unknown key `{key}` in [package.metadata.manganis] of {}. Supported keys are {}",
+                    path.display(),
+                    KNOWN_KEYS.join(", ")
+                );
+            }
+            known
+        });
+        let config: Self = toml::Value::Table(table).try_into().with_context(|| {
+            format!(
+                "Failed to parse [package.metadata.manganis] in {}",
+                path.display()
+            )
+        })?;
+
+        if let Some(quality) = config.image_quality {
+            anyhow::ensure!(
+                quality <= 100,
+                "image-quality in {} must be between 0 and 100, found {quality}",
+                path.display()
+            );
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the format images are converted to by default
+    pub fn image_format(&self) -> Option<ImageType> {
+        self.image_format
+    }
+
+    /// Returns the quality lossy images are encoded with by default
+    pub fn image_quality(&self) -> Option<u8> {
+        self.image_quality
+    }
+
+    /// Returns whether css files are minified by default
+    pub fn minify_css(&self) -> Option<bool> {
+        self.minify_css
+    }
+
+    /// Returns whether javascript files are minified by default
+    pub fn minify_js(&self) -> Option<bool> {
+        self.minify_js
+    }
+
+    /// Applies the defaults to the options of an asset. Options set in the `mg!` call are applied afterwards, so they take precedence
+    pub fn apply_defaults(&self, options: &mut FileOptions) {
+        match options {
+            FileOptions::Css(css) => {
+                if let Some(minify) = self.minify_css {
+                    *css = CssOptions::new(minify);
+                }
+            }
+            FileOptions::Js(js) => {
+                if let Some(minify) = self.minify_js {
+                    js.set_minify(minify);
+                }
+            }
+            FileOptions::Image(image) => {
+                if let Some(quality) = self.image_quality {
+                    image.set_quality(Some(quality));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the options of an `image(..)` asset before the options of the `mg!` call are applied
+    pub fn image_options(&self) -> ImageOptions {
+        let mut options = ImageOptions::new(self.image_format.unwrap_or(ImageType::Avif), None);
+        options.set_quality(self.image_quality);
+        options
+    }
+
+    /// Returns the directory relative asset paths are resolved from. This is relative to the crate root
+    pub fn base_dir(&self) -> Option<&Path> {
+        self.base_dir.as_deref()
+    }
+
+    /// Returns the absolute directory relative asset paths are resolved from
+    ///
+    /// This is only used in the macro
+    pub fn asset_dir(&self) -> PathBuf {
+        let manifest_dir = manifest_dir();
+        match &self.base_dir {
+            Some(base_dir) => manifest_dir.join(base_dir),
+            None => manifest_dir,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+fn default_enabled() -> bool {
+    false
+}
+
+fn default_min_size() -> u64 {
+    1024
+}
+
+/// The settings for writing precompressed variants of text assets
+///
+/// ```toml
+/// [precompress]
+/// enabled = true
+/// min_size = 1024
+/// ```
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PrecompressConfig {
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default = "default_min_size")]
+    min_size: u64,
+}
+
+impl Default for PrecompressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            min_size: default_min_size(),
+        }
+    }
+}
+
+impl PrecompressConfig {
+    /// Sets whether `.br` and `.gz` variants are written next to compressible assets. Defaults to false
+    pub fn with_enabled(&self, enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..self.clone()
+        }
+    }
+
+    /// Returns whether `.br` and `.gz` variants are written next to compressible assets
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets the size in bytes a processed file must have before variants are written. Defaults to 1024
+    pub fn with_min_size(&self, min_size: u64) -> Self {
+        Self {
+            min_size,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the size in bytes a processed file must have before variants are written
+    pub fn min_size(&self) -> u64 {
+        self.min_size
+    }
+}
+
+/// An encoding precompressed variants of assets are written in
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    /// Brotli, written to `<name>.br`
+    Brotli,
+    /// Gzip, written to `<name>.gz`
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Every encoding, in order of preference
+    pub const ALL: [ContentEncoding; 2] = [ContentEncoding::Brotli, ContentEncoding::Gzip];
+
+    /// Returns the name of the encoding in the `Content-Encoding` header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// Returns the extension that is added to the name of the file
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gz",
+        }
+    }
+
+    /// Returns the name of the variant of a file, like `style1234.css.br`
+    pub fn variant_name(&self, unique_name: &str) -> String {
+        format!("{unique_name}.{}", self.extension())
+    }
+}
+
+/// Returns whether files with the extension compress well, like text and wasm files
+pub fn is_compressible(extension: Option<&str>) -> bool {
+    matches!(
+        extension,
+        Some("css" | "js" | "mjs" | "json" | "svg" | "html" | "txt" | "xml" | "wasm")
+    )
+}
@@ -0,0 +1,278 @@
+//! The binary format of the data stored in the manganis link section.
+//!
+//! Every asset the macro collects is written to the link section as a single record. The linker concatenates the records from every crate into one section, possibly with padding in between, so each record is framed and can be found without knowing where the previous one ended:
+//!
+//! | Field            | Size               | Description                                                  |
+//! |------------------|--------------------|--------------------------------------------------------------|
+//! | magic            | 4 bytes            | [`MAGIC`]                                                    |
+//! | format version   | 2 bytes (LE)       | The [`FORMAT_VERSION`] of the writer                         |
+//! | package length   | 1 byte             | Length of the package name                                   |
+//! | package          | `package length`   | The name of the crate that declared the asset                |
+//! | version length   | 1 byte             | Length of the manganis version                               |
+//! | version          | `version length`   | The version of manganis-common that wrote the record         |
+//! | payload length   | 4 bytes (LE)       | Length of the payload                                        |
+//! | payload          | `payload length`   | The [`AssetType`] serialized as JSON                         |
+//!
+//! # Compatibility
+//!
+//! The header (everything before the payload) is frozen: every future version of manganis will write it in exactly this layout. This lets any reader find, skip and describe records it does not understand instead of failing silently.
+//!
+//! The [`FORMAT_VERSION`] is bumped whenever the payload changes in a way older readers cannot decode, for example when a new [`AssetType`] variant is added. Adding a field with a default value does not require a new version. A reader accepts records with a format version between [`MIN_SUPPORTED_FORMAT_VERSION`] and [`FORMAT_VERSION`], and reports every other record as a [`SectionError`] that names the package and manganis version that wrote it.
+
+use std::fmt::Display;
+
+use crate::AssetType;
+
+/// The bytes every record in the link section starts with
+pub const MAGIC: [u8; 4] = *b"MGAS";
+
+/// The version of the record format written by this version of manganis
+///
+/// - 1: The first versioned format
+/// - 2: Adds [`AssetType::Folder`]
+/// - 3: Adds [`crate::FileOptions::Js`]
+pub const FORMAT_VERSION: u16 = 3;
+
+/// The oldest version of the record format this version of manganis can read
+pub const MIN_SUPPORTED_FORMAT_VERSION: u16 = 1;
+
+/// The version of manganis-common that writes records
+const MANGANIS_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Encode an asset into a record for the link section
+pub fn encode_asset(package: &str, asset: &AssetType) -> Vec<u8> {
+    let payload = serde_json::to_vec(asset).unwrap();
+    let package = truncate_field(package);
+    let version = truncate_field(MANGANIS_VERSION);
+
+    let mut record = Vec::with_capacity(
+        MAGIC.len() + 2 + 1 + package.len() + 1 + version.len() + 4 + payload.len(),
+    );
+    record.extend_from_slice(&MAGIC);
+    record.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    record.push(package.len() as u8);
+    record.extend_from_slice(package);
+    record.push(version.len() as u8);
+    record.extend_from_slice(version);
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&payload);
+    record
+}
+
+fn truncate_field(field: &str) -> &[u8] {
+    let bytes = field.as_bytes();
+    &bytes[..bytes.len().min(u8::MAX as usize)]
+}
+
+/// A record decoded from the link section
+#[derive(Debug, PartialEq, Clone)]
+pub struct SectionRecord {
+    format_version: u16,
+    package: String,
+    manganis_version: String,
+    asset: AssetType,
+}
+
+impl SectionRecord {
+    /// Returns the format version the record was written with
+    pub fn format_version(&self) -> u16 {
+        self.format_version
+    }
+
+    /// Returns the name of the crate that declared the asset
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// Returns the version of manganis that wrote the record
+    pub fn manganis_version(&self) -> &str {
+        &self.manganis_version
+    }
+
+    /// Returns the asset stored in the record
+    pub fn asset(&self) -> &AssetType {
+        &self.asset
+    }
+
+    /// Converts the record into the asset it contains
+    pub fn into_asset(self) -> AssetType {
+        self.asset
+    }
+}
+
+/// An error while decoding the link section
+#[derive(Debug, PartialEq, Clone)]
+pub enum SectionError {
+    /// The record was written with a format version this version of manganis cannot read
+    UnsupportedVersion {
+        /// The format version of the record
+        format_version: u16,
+        /// The name of the crate that declared the asset
+        package: String,
+        /// The version of manganis that wrote the record
+        manganis_version: String,
+    },
+    /// The record has a supported format version, but the payload could not be decoded
+    InvalidPayload {
+        /// The name of the crate that declared the asset
+        package: String,
+        /// The version of manganis that wrote the record
+        manganis_version: String,
+        /// The error from decoding the payload
+        error: String,
+    },
+    /// The section ended in the middle of a record
+    Truncated,
+    /// The section contains data that is not framed, which is written by older versions of manganis
+    Unframed,
+}
+
+impl Display for SectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SectionError::UnsupportedVersion {
+                format_version,
+                package,
+                manganis_version,
+            } => write!(
+                f,
+                "`{package}` uses manganis {manganis_version} which writes asset format version {format_version}, but this tool supports versions {MIN_SUPPORTED_FORMAT_VERSION} to {FORMAT_VERSION}. Make sure all dependencies use a compatible version of manganis"
+            ),
+            SectionError::InvalidPayload {
+                package,
+                manganis_version,
+                error,
+            } => write!(
+                f,
+                "failed to decode an asset from `{package}` (manganis {manganis_version}): {error}"
+            ),
+            SectionError::Truncated => write!(f, "the asset section ends in the middle of an asset"),
+            SectionError::Unframed => write!(
+                f,
+                "found assets written by an older version of manganis. Make sure all dependencies use a compatible version of manganis"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SectionError {}
+
+/// The result of decoding a link section
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct DecodedSection {
+    /// The records that were decoded successfully
+    pub records: Vec<SectionRecord>,
+    /// The errors for any records that could not be decoded
+    pub errors: Vec<SectionError>,
+}
+
+/// Decode all records in the data from a link section
+///
+/// Padding the linker inserts between records is skipped. Records that cannot be read are reported in [`DecodedSection::errors`] and do not stop the rest of the section from being decoded.
+pub fn decode_section(data: &[u8]) -> DecodedSection {
+    let mut decoded = DecodedSection::default();
+    let mut unframed = false;
+    let mut remaining = data;
+
+    while !remaining.is_empty() {
+        if !remaining.starts_with(&MAGIC) {
+            // Some platforms pad the section with null bytes. Anything else is data we do not understand
+            unframed |= remaining[0] != 0;
+            remaining = &remaining[1..];
+            continue;
+        }
+
+        match read_record(&remaining[MAGIC.len()..]) {
+            Some((record, rest)) => {
+                match record {
+                    Ok(record) => decoded.records.push(record),
+                    Err(error) => decoded.errors.push(error),
+                }
+                remaining = rest;
+            }
+            None => {
+                decoded.errors.push(SectionError::Truncated);
+                break;
+            }
+        }
+    }
+
+    if unframed {
+        decoded.errors.push(SectionError::Unframed);
+    }
+
+    decoded
+}
+
+/// Find the complete records in data that may contain unrelated bytes between them
+///
+/// This is used to recover assets from data that is not a link section, like the constants of an LLVM bitcode module. Unlike [`decode_section`], bytes that are not part of a record are ignored.
+pub fn find_records(data: &[u8]) -> Vec<&[u8]> {
+    let mut records = Vec::new();
+    let mut position = 0;
+
+    while position + MAGIC.len() <= data.len() {
+        let remaining = &data[position..];
+        if remaining.starts_with(&MAGIC) {
+            // The magic may appear by chance, so only accept records with a payload we can read
+            if let Some((record, rest)) = read_record(&remaining[MAGIC.len()..]) {
+                if !matches!(record, Err(SectionError::InvalidPayload { .. })) {
+                    let len = remaining.len() - rest.len();
+                    records.push(&remaining[..len]);
+                    position += len;
+                    continue;
+                }
+            }
+        }
+        position += 1;
+    }
+
+    records
+}
+
+type ReadRecord<'a> = (Result<SectionRecord, SectionError>, &'a [u8]);
+
+fn read_record(data: &[u8]) -> Option<ReadRecord<'_>> {
+    let (format_version, data) = split(data, 2)?;
+    let format_version = u16::from_le_bytes([format_version[0], format_version[1]]);
+    let (package, data) = read_field(data)?;
+    let (manganis_version, data) = read_field(data)?;
+    let (payload_len, data) = split(data, 4)?;
+    let payload_len = u32::from_le_bytes(payload_len.try_into().unwrap()) as usize;
+    let (payload, rest) = split(data, payload_len)?;
+
+    if !(MIN_SUPPORTED_FORMAT_VERSION..=FORMAT_VERSION).contains(&format_version) {
+        let error = SectionError::UnsupportedVersion {
+            format_version,
+            package,
+            manganis_version,
+        };
+        return Some((Err(error), rest));
+    }
+
+    let record = match serde_json::from_slice(payload) {
+        Ok(asset) => Ok(SectionRecord {
+            format_version,
+            package,
+            manganis_version,
+            asset,
+        }),
+        Err(error) => Err(SectionError::InvalidPayload {
+            package,
+            manganis_version,
+            error: error.to_string(),
+        }),
+    };
+
+    Some((record, rest))
+}
+
+fn read_field(data: &[u8]) -> Option<(String, &[u8])> {
+    let (len, data) = split(data, 1)?;
+    let (field, data) = split(data, len[0] as usize)?;
+    Some((String::from_utf8_lossy(field).to_string(), data))
+}
+
+fn split(data: &[u8], at: usize) -> Option<(&[u8], &[u8])> {
+    (data.len() >= at).then(|| data.split_at(at))
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{cache::asset_cache_dir, Fetcher, Integrity};
+
+/// The environment variable that disables downloading remote assets. If it is set to anything other than `0` or `false`, remote assets are only read from the vendor cache
+pub const OFFLINE_ENV: &str = "MANGANIS_OFFLINE";
+
+/// The environment variable that sets the path of the lockfile remote assets are pinned in
+pub const LOCKFILE_ENV: &str = "MANGANIS_LOCKFILE";
+
+/// The environment variable that sets the directory remote assets are vendored into
+pub const VENDOR_DIR_ENV: &str = "MANGANIS_VENDOR_DIR";
+
+/// The name of the lockfile remote assets are pinned in
+pub const LOCKFILE_NAME: &str = "manganis.lock";
+
+const LOCKFILE_VERSION: u32 = 1;
+
+/// Returns true if remote assets may only be read from the vendor cache
+pub fn offline() -> bool {
+    std::env::var(OFFLINE_ENV).is_ok_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+}
+
+/// The directory remote assets are vendored into. Files in this directory are named by the SHA-256 of their content
+pub fn vendor_dir() -> PathBuf {
+    match std::env::var_os(VENDOR_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => asset_cache_dir().join("vendor"),
+    }
+}
+
+/// A remote asset pinned in the lockfile
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedAsset {
+    url: Url,
+    sha256: String,
+    fetched_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+}
+
+impl LockedAsset {
+    /// Returns the URL of the asset
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Returns the hex encoded SHA-256 of the content of the asset
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /// Returns when the asset was downloaded as an RFC 3339 date in UTC
+    pub fn fetched_at(&self) -> &str {
+        &self.fetched_at
+    }
+
+    /// Returns the content type the server sent with the asset
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the last modified date the server sent with the asset
+    pub fn last_modified(&self) -> Option<&str> {
+        self.last_modified.as_deref()
+    }
+
+    /// Returns the path of the content of the asset in the vendor cache
+    pub fn vendored_path(&self) -> PathBuf {
+        vendor_dir().join(&self.sha256)
+    }
+}
+
+/// The `manganis.lock` file that pins the content of every remote asset an application uses
+///
+/// ```toml
+/// version = 1
+///
+/// [[asset]]
+/// url = "https://rustacean.net/assets/rustacean-flat-happy.png"
+/// sha256 = "2b7b..."
+/// fetched-at = "2024-06-01T12:00:00Z"
+/// content-type = "image/png"
+/// ```
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Lockfile {
+    version: u32,
+    #[serde(default, rename = "asset")]
+    assets: Vec<LockedAsset>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            assets: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Returns the path of the lockfile for the current build
+    ///
+    /// This is the path in the [`LOCKFILE_ENV`] environment variable if it is set. Otherwise this looks for an existing `manganis.lock` in the crate directory (or the current directory outside of a build) and its parents, and falls back to the directory of the workspace `Cargo.lock`.
+    ///
+    /// The sources of dependencies from a registry or git are never searched, so their lockfile is not written next to their sources. Cargo compiles them in the workspace root, so their assets are pinned in the lockfile of the workspace they are built in.
+    pub fn path() -> PathBuf {
+        if let Some(path) = std::env::var_os(LOCKFILE_ENV) {
+            return PathBuf::from(path);
+        }
+
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        let start = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .filter(|dir| !is_dependency_source(dir))
+            .unwrap_or_else(|| current_dir.clone());
+        let mut ancestors = start.ancestors().filter(|dir| !is_dependency_source(dir));
+
+        if let Some(dir) = ancestors
+            .clone()
+            .find(|dir| dir.join(LOCKFILE_NAME).is_file())
+        {
+            return dir.join(LOCKFILE_NAME);
+        }
+        match ancestors.find(|dir| dir.join("Cargo.lock").is_file()) {
+            Some(root) => root.join(LOCKFILE_NAME),
+            None if is_dependency_source(&current_dir) => asset_cache_dir().join(LOCKFILE_NAME),
+            None => current_dir.join(LOCKFILE_NAME),
+        }
+    }
+
+    /// Reads a lockfile. Returns an empty lockfile if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to read {}", path.display()))
+            }
+        };
+        let lockfile: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        anyhow::ensure!(
+            lockfile.version <= LOCKFILE_VERSION,
+            "{} was written by a newer version of manganis (lockfile version {})",
+            path.display(),
+            lockfile.version
+        );
+        Ok(lockfile)
+    }
+
+    /// Writes the lockfile. The assets are sorted by URL so the file is stable under version control
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let mut lockfile = self.clone();
+        lockfile
+            .assets
+            .sort_by(|a, b| a.url.as_str().cmp(b.url.as_str()));
+        let contents = format!(
+            "# This file is generated by manganis. It pins the content of remote assets.\n{}",
+            toml::to_string_pretty(&lockfile)?
+        );
+
+        // Write to a temporary file first so other builds never read a partial lockfile
+        let tmp = path.with_extension(format!("lock.{}", std::process::id()));
+        std::fs::write(&tmp, contents)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Returns every pinned asset
+    pub fn assets(&self) -> &[LockedAsset] {
+        &self.assets
+    }
+
+    /// Returns the pinned asset for a URL
+    pub fn get(&self, url: &Url) -> Option<&LockedAsset> {
+        self.assets.iter().find(|asset| &asset.url == url)
+    }
+
+    /// Pins an asset, replacing the entry for the same URL
+    pub fn insert(&mut self, asset: LockedAsset) {
+        self.assets.retain(|existing| existing.url != asset.url);
+        self.assets.push(asset);
+    }
+}
+
+/// Returns true if a directory is inside the sources cargo downloads dependencies into
+fn is_dependency_source(dir: &Path) -> bool {
+    home::cargo_home().is_ok_and(|home| {
+        dir.starts_with(home.join("registry")) || dir.starts_with(home.join("git"))
+    })
+}
+
+/// Returns the lockfile entry of a remote asset
+///
+/// If the asset is not pinned yet, it is downloaded into the vendor cache and added to the lockfile. If it is pinned but missing from the vendor cache, it is downloaded again and must match the pinned SHA-256.
+/// In [`offline`] mode this fails instead of downloading anything.
+pub fn remote_asset(url: &Url) -> anyhow::Result<LockedAsset> {
+    resolve(url, None).map(|(asset, _)| asset)
+}
+
+/// Returns the lockfile entry of a remote asset whose content must match an integrity. See [`remote_asset`]
+///
+/// A download that does not match the integrity is rejected before it is vendored or pinned.
+pub fn verified_remote_asset(
+    url: &Url,
+    integrity: Option<&Integrity>,
+) -> anyhow::Result<LockedAsset> {
+    resolve(url, integrity).map(|(asset, _)| asset)
+}
+
+/// Reads the content of a remote asset from the vendor cache. See [`remote_asset`]
+pub fn read_remote_asset(url: &Url) -> anyhow::Result<Vec<u8>> {
+    resolve(url, None).map(|(_, bytes)| bytes)
+}
+
+/// Downloads a remote asset again and pins its current content in the lockfile
+pub fn update_remote_asset(url: &Url) -> anyhow::Result<LockedAsset> {
+    ensure_online(url)?;
+    let (asset, bytes) = download(url)?;
+    write_vendored(&asset, &bytes)?;
+    let asset = pin(asset)?;
+    resolved_assets()
+        .lock()
+        .unwrap()
+        .insert((Lockfile::path(), url.clone()), Ok((asset.clone(), bytes)));
+    Ok(asset)
+}
+
+type Resolved = Result<(LockedAsset, Vec<u8>), String>;
+
+/// The remote assets this process already resolved, by lockfile and URL. Every asset is read and hashed once, and a failed download is not retried
+fn resolved_assets() -> &'static Mutex<HashMap<(PathBuf, Url), Resolved>> {
+    static RESOLVED: OnceLock<Mutex<HashMap<(PathBuf, Url), Resolved>>> = OnceLock::new();
+    RESOLVED.get_or_init(Default::default)
+}
+
+fn resolve(url: &Url, integrity: Option<&Integrity>) -> anyhow::Result<(LockedAsset, Vec<u8>)> {
+    let key = (Lockfile::path(), url.clone());
+    let cached = resolved_assets().lock().unwrap().get(&key).cloned();
+    let (asset, bytes) = match cached {
+        Some(resolved) => resolved.map_err(anyhow::Error::msg)?,
+        None => match resolve_uncached(&key.0, url, integrity) {
+            Ok(resolved) => {
+                resolved_assets()
+                    .lock()
+                    .unwrap()
+                    .insert(key, Ok(resolved.clone()));
+                resolved
+            }
+            Err(err) => {
+                // A mismatch only fails for this integrity, so it is not remembered
+                if integrity.is_none() {
+                    resolved_assets()
+                        .lock()
+                        .unwrap()
+                        .insert(key, Err(format!("{err:#}")));
+                }
+                return Err(err);
+            }
+        },
+    };
+    if let Some(integrity) = integrity {
+        integrity
+            .verify(&bytes)
+            .with_context(|| format!("The remote asset {url} does not match its integrity"))?;
+    }
+    Ok((asset, bytes))
+}
+
+fn resolve_uncached(
+    lockfile_path: &Path,
+    url: &Url,
+    integrity: Option<&Integrity>,
+) -> anyhow::Result<(LockedAsset, Vec<u8>)> {
+    let lockfile = Lockfile::load(lockfile_path)?;
+
+    if let Some(asset) = lockfile.get(url) {
+        if let Some(bytes) = read_vendored(asset) {
+            return Ok((asset.clone(), bytes));
+        }
+
+        // The asset is pinned, but this machine has not downloaded it yet
+        ensure_online(url)?;
+        let (downloaded, bytes) = download(url)?;
+        anyhow::ensure!(
+            downloaded.sha256 == asset.sha256,
+            "The content of {url} changed since it was pinned in {}.\nExpected SHA-256 {}, found {}.\nRun `cargo manganis prefetch --update` to pin the new content.",
+            lockfile_path.display(),
+            asset.sha256,
+            downloaded.sha256
+        );
+        write_vendored(asset, &bytes)?;
+        return Ok((asset.clone(), bytes));
+    }
+
+    ensure_online(url)?;
+    let (asset, bytes) = download(url)?;
+    // Never trust a download that does not match what the crate expects
+    if let Some(integrity) = integrity {
+        integrity
+            .verify(&bytes)
+            .with_context(|| format!("The remote asset {url} does not match its integrity"))?;
+    }
+    write_vendored(&asset, &bytes)?;
+    let asset = pin(asset)?;
+    Ok((asset, bytes))
+}
+
+fn ensure_online(url: &Url) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !offline(),
+        "The remote asset {url} is not in the vendor cache and {OFFLINE_ENV} is set.\nRun `cargo manganis prefetch` with network access to download it into {}.",
+        vendor_dir().display()
+    );
+    Ok(())
+}
+
+/// Adds the asset to the lockfile
+fn pin(asset: LockedAsset) -> anyhow::Result<LockedAsset> {
+    let path = Lockfile::path();
+    // Macros expand in parallel in different processes, so the lockfile is read and written under a file lock
+    let _guard = lock_lockfiles()?;
+    let mut lockfile = Lockfile::load(&path)?;
+    if lockfile.get(&asset.url) != Some(&asset) {
+        tracing::info!("Pinning {} in {}", asset.url, path.display());
+        lockfile.insert(asset.clone());
+        lockfile.save(&path)?;
+    }
+    Ok(asset)
+}
+
+/// Takes an exclusive lock for updating lockfiles. The lock is released when the returned file is dropped
+fn lock_lockfiles() -> anyhow::Result<File> {
+    let dir = vendor_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join("lockfile.guard");
+    let file = File::create(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(file)
+}
+
+fn download(url: &Url) -> anyhow::Result<(LockedAsset, Vec<u8>)> {
+    tracing::info!("Downloading remote asset {url}");
+    let file = Fetcher::current()?.get(url)?;
+    let asset = LockedAsset {
+        url: url.clone(),
+        sha256: sha256_hex(&file.bytes),
+        fetched_at: format_utc(SystemTime::now()),
+        content_type: file.content_type,
+        last_modified: file.last_modified,
+    };
+    Ok((asset, file.bytes))
+}
+
+/// Reads the vendored content of an asset. Returns `None` if it is missing or does not match the pinned hash
+fn read_vendored(asset: &LockedAsset) -> Option<Vec<u8>> {
+    let bytes = std::fs::read(asset.vendored_path()).ok()?;
+    if sha256_hex(&bytes) != asset.sha256 {
+        tracing::warn!(
+            "The vendored copy of {} is corrupted, ignoring it",
+            asset.url
+        );
+        return None;
+    }
+    Some(bytes)
+}
+
+fn write_vendored(asset: &LockedAsset, bytes: &[u8]) -> anyhow::Result<()> {
+    let path = asset.vendored_path();
+    if path.is_file() {
+        return Ok(());
+    }
+    let dir = vendor_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let tmp = path.with_extension(std::process::id().to_string());
+    std::fs::write(&tmp, bytes).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Formats a time as an RFC 3339 date in UTC, like `2024-06-01T12:00:00Z`
+fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Convert days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
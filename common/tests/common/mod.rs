@@ -0,0 +1,58 @@
+// Helpers shared by the integration tests. Each test binary only uses some of them
+#![allow(dead_code)]
+
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// A unique directory for the files created by a test. The directory is removed when it is dropped
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Creates an empty directory for the test with the given name
+    pub fn new(name: &str) -> Self {
+        let dir =
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir.canonicalize().unwrap())
+    }
+
+    /// Creates a directory with the given files, as paths relative to the directory and their contents
+    pub fn with_files(name: &str, files: &[(&str, impl AsRef<[u8]>)]) -> Self {
+        let dir = Self::new(name);
+        for (file, contents) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, contents).unwrap();
+        }
+        dir
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<&TestDir> for PathBuf {
+    fn from(dir: &TestDir) -> Self {
+        dir.0.clone()
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::TestDir;
+use manganis_common::{Config, FileAsset, FileSource, FolderAsset, PackageConfig, CONFIG_PATH_ENV};
+use std::path::PathBuf;
+
+#[test]
+fn each_config_gets_its_own_file_in_the_target_dir() {
+    let target_dir = TestDir::new("save-for-build");
+    let app = Config::default().with_assets_serve_location("/app/");
+    let docs = Config::default().with_assets_serve_location("/docs/");
+
+    let app_path = app.save_for_build(&target_dir);
+    let docs_path = docs.save_for_build(&target_dir);
+    assert_ne!(app_path, docs_path);
+    assert!(app_path.starts_with(target_dir.join("manganis")));
+    assert_eq!(app.save_for_build(&target_dir), app_path);
+
+    // Both files keep their own config
+    let read = |path: &PathBuf| toml::from_str::<Config>(&std::fs::read_to_string(path).unwrap());
+    assert_eq!(read(&app_path).unwrap(), app);
+    assert_eq!(read(&docs_path).unwrap(), docs);
+}
+
+#[test]
+fn saving_the_same_config_keeps_the_file() {
+    let dir = TestDir::new("unchanged");
+    let path = dir.join("config.toml");
+    let config = Config::default().with_dev(true);
+    config.save_to(&path);
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    config.save_to(&path);
+    // The macro reruns when the file changes, so an identical config must not touch it
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().modified().unwrap(),
+        modified
+    );
+}
+
+// Changing the environment is not thread safe, so everything that reads MANGANIS_CONFIG runs in one test
+#[test]
+fn the_environment_picks_the_config_of_the_build() {
+    let dir = TestDir::new("environment");
+    std::env::remove_var(CONFIG_PATH_ENV);
+    let global = Config::current();
+    assert_eq!(Config::config_path(), Config::global_config_path());
+
+    let app = Config::default().with_assets_serve_location("/app/");
+    let app_path = app.save_for_build(&dir);
+    std::env::set_var(CONFIG_PATH_ENV, &app_path);
+    assert_eq!(Config::config_path(), app_path);
+    assert_eq!(Config::current(), app);
+    assert_eq!(
+        Config::current().asset_location("logo.png"),
+        "/app/logo.png"
+    );
+
+    std::fs::create_dir_all(dir.join("static")).unwrap();
+    std::fs::write(dir.join("static/logo.svg"), "<svg></svg>").unwrap();
+    let file = FileAsset::new(FileSource::Local(dir.join("static/logo.svg")));
+    let folder = FolderAsset::from_dir("static", &dir, &PackageConfig::default()).unwrap();
+
+    let docs = Config::default().with_assets_serve_location("/docs/");
+    std::env::set_var(CONFIG_PATH_ENV, docs.save_for_build(&dir));
+    assert_eq!(Config::current(), docs);
+
+    // Assets keep the location of the build that collected them, so a build tool reading them later finds the same location
+    assert_eq!(
+        file.served_location(),
+        format!("/app/{}", file.location().unique_name())
+    );
+    assert_eq!(
+        folder.served_location(),
+        format!("/app/{}", folder.unique_name())
+    );
+    assert_eq!(
+        folder.served_file_location(&folder.files()[0]),
+        format!("/app/{}/logo.svg", folder.unique_name())
+    );
+
+    // A build config that cannot be read falls back to the global config
+    std::env::set_var(CONFIG_PATH_ENV, dir.join("missing.toml"));
+    assert_eq!(Config::current(), global);
+
+    std::env::remove_var(CONFIG_PATH_ENV);
+    assert_eq!(Config::current(), global);
+}
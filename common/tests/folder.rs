@@ -0,0 +1,153 @@
+mod common;
+
+use common::TestDir;
+use manganis_common::{AssetError, CssOptions, FileOptions, FolderAsset, PackageConfig};
+
+// Creates the files in a unique directory for each test
+fn tree(name: &str, files: &[&str]) -> TestDir {
+    let files: Vec<_> = files
+        .iter()
+        .map(|file| (*file, format!("/* {file} */")))
+        .collect();
+    TestDir::with_files(name, &files)
+}
+
+fn relative_paths(folder: &FolderAsset) -> Vec<&str> {
+    folder
+        .files()
+        .iter()
+        .map(|file| file.relative_path())
+        .collect()
+}
+
+#[test]
+fn dir_collects_nested_files() {
+    let base = tree(
+        "dir",
+        &["static/b.txt", "static/a.txt", "static/icons/arrow.txt"],
+    );
+    let folder = FolderAsset::from_dir("static", &base, &PackageConfig::default()).unwrap();
+    assert_eq!(
+        relative_paths(&folder),
+        ["a.txt", "b.txt", "icons/arrow.txt"]
+    );
+    assert_eq!(folder.source(), base.join("static").canonicalize().unwrap());
+    assert_eq!(folder.pattern(), None);
+    assert!(folder.unique_name().starts_with("static"));
+
+    let file = folder.get("icons/arrow.txt").unwrap();
+    assert_eq!(
+        folder.file_unique_name(file),
+        format!("{}/icons/arrow.txt", folder.unique_name())
+    );
+}
+
+#[test]
+fn dir_rejects_missing_paths_and_files() {
+    let base = tree("dir-errors", &["file.txt"]);
+    assert!(matches!(
+        FolderAsset::from_dir("missing", &base, &PackageConfig::default()),
+        Err(AssetError::NotFoundRelative(..))
+    ));
+    assert!(matches!(
+        FolderAsset::from_dir("file.txt", &base, &PackageConfig::default()),
+        Err(AssetError::NotDirectory(_))
+    ));
+}
+
+#[test]
+fn glob_keeps_the_structure_below_the_wildcard() {
+    let base = tree(
+        "glob",
+        &[
+            "assets/icons/arrow.css",
+            "assets/icons/nested/close.css",
+            "assets/icons/readme.txt",
+            "assets/other.css",
+        ],
+    );
+    let folder =
+        FolderAsset::from_glob("assets/icons/**/*.css", &base, &PackageConfig::default()).unwrap();
+    assert_eq!(relative_paths(&folder), ["arrow.css", "nested/close.css"]);
+    assert_eq!(
+        folder.source(),
+        base.join("assets/icons").canonicalize().unwrap()
+    );
+    assert_eq!(folder.pattern(), Some("assets/icons/**/*.css"));
+}
+
+#[test]
+fn glob_rejects_patterns_without_matches_or_wildcards() {
+    let base = tree("glob-errors", &["assets/style.css"]);
+    assert!(matches!(
+        FolderAsset::from_glob("assets/*.js", &base, &PackageConfig::default()),
+        Err(AssetError::InvalidGlob(..))
+    ));
+    assert!(matches!(
+        FolderAsset::from_glob("assets/style.css", &base, &PackageConfig::default()),
+        Err(AssetError::InvalidGlob(..))
+    ));
+    assert!(matches!(
+        FolderAsset::from_glob("missing/*.css", &base, &PackageConfig::default()),
+        Err(AssetError::NotFoundRelative(..))
+    ));
+}
+
+#[test]
+fn fonts_in_a_folder_are_copied() {
+    let base = tree("fonts", &["fonts/inter.woff2"]);
+    let folder = FolderAsset::from_dir("fonts", &base, &PackageConfig::default()).unwrap();
+    let file = folder.get("inter.woff2").unwrap();
+    assert!(matches!(file.asset().options(), FileOptions::Other(_)));
+    assert_eq!(file.asset().location().unique_name(), "inter.woff2");
+}
+
+#[test]
+fn the_name_changes_with_the_files() {
+    let base = tree("rename", &["static/a.txt"]);
+    let folder = FolderAsset::from_dir("static", &base, &PackageConfig::default()).unwrap();
+    assert_eq!(
+        FolderAsset::from_dir("static", &base, &PackageConfig::default())
+            .unwrap()
+            .unique_name(),
+        folder.unique_name()
+    );
+
+    std::fs::write(base.join("static/b.txt"), "b").unwrap();
+    let changed = FolderAsset::from_dir("static", &base, &PackageConfig::default()).unwrap();
+    assert_ne!(changed.unique_name(), folder.unique_name());
+}
+
+#[test]
+fn skips_hidden_files_and_symlinked_directories() {
+    let base = tree(
+        "hidden",
+        &["static/a.txt", "static/.DS_Store", "static/.git/config"],
+    );
+    // A symlink to a parent would make the walk loop forever if it was followed
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(base.join("static"), base.join("static/loop")).unwrap();
+
+    let folder = FolderAsset::from_dir("static", &base, &PackageConfig::default()).unwrap();
+    assert_eq!(relative_paths(&folder), ["a.txt"]);
+    let folder = FolderAsset::from_glob("static/**/*", &base, &PackageConfig::default()).unwrap();
+    assert_eq!(relative_paths(&folder), ["a.txt"]);
+}
+
+#[test]
+fn applies_the_package_defaults_to_each_file() {
+    let base = tree("defaults", &["static/style.css"]);
+    std::fs::write(
+        base.join("Cargo.toml"),
+        "[package]\nname = \"app\"\n\n[package.metadata.manganis]\nminify-css = false\n",
+    )
+    .unwrap();
+    let config = PackageConfig::from_manifest(base.join("Cargo.toml")).unwrap();
+
+    let folder = FolderAsset::from_dir("static", &base, &config).unwrap();
+    let file = folder.get("style.css").unwrap();
+    assert_eq!(
+        file.asset().options(),
+        &FileOptions::Css(CssOptions::new(false))
+    );
+}
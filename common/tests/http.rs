@@ -0,0 +1,169 @@
+use manganis_common::{Fetcher, HttpConfig};
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use url::Url;
+
+enum Reply {
+    Status(u16, &'static str),
+    Hang,
+}
+
+struct TestServer {
+    url: Url,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    fn host(&self) -> String {
+        format!("127.0.0.1:{}", self.url.port().unwrap())
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Serves the replies in order, one per connection, and then `200 OK` for every other request
+fn serve(replies: Vec<Reply>) -> TestServer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!(
+        "http://{}/style.css",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = requests.clone();
+    std::thread::spawn(move || {
+        let mut replies = VecDeque::from(replies);
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            recorded.lock().unwrap().push(request);
+
+            let (status, body) = match replies.pop_front() {
+                Some(Reply::Status(status, body)) => (status, body),
+                Some(Reply::Hang) => {
+                    std::thread::sleep(Duration::from_secs(5));
+                    continue;
+                }
+                None => (200, "body { color: red; }"),
+            };
+            let response = format!(
+                "HTTP/1.1 {status} Test\r\nContent-Type: text/css\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    TestServer { url, requests }
+}
+
+fn fast_config() -> HttpConfig {
+    HttpConfig::default()
+        .with_timeout(Duration::from_secs(2))
+        .with_retry_backoff(Duration::from_millis(10))
+}
+
+#[test]
+fn downloads_file_with_host_headers() {
+    let server = serve(vec![]);
+    std::env::set_var("MANGANIS_TEST_TOKEN", "secret");
+    let config = fast_config().with_header(
+        server.host(),
+        "Authorization",
+        "Bearer ${MANGANIS_TEST_TOKEN}",
+    );
+
+    let file = Fetcher::new(config).unwrap().get(&server.url).unwrap();
+    assert_eq!(file.bytes, b"body { color: red; }");
+    assert_eq!(file.content_type.as_deref(), Some("text/css"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let request = requests[0].to_lowercase();
+    assert!(
+        request.contains("authorization: bearer secret"),
+        "{request}"
+    );
+    assert!(request.contains("user-agent: manganis/"), "{request}");
+}
+
+#[test]
+fn headers_are_only_sent_to_their_host() {
+    let server = serve(vec![]);
+    let config = fast_config().with_header("example.com", "Authorization", "Bearer secret");
+
+    Fetcher::new(config).unwrap().get(&server.url).unwrap();
+    assert!(!server.requests()[0]
+        .to_lowercase()
+        .contains("authorization"));
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let server = serve(vec![Reply::Status(404, "not found")]);
+
+    let err = Fetcher::new(fast_config())
+        .unwrap()
+        .get(&server.url)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("404"), "{err:#}");
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn server_errors_are_retried() {
+    let server = serve(vec![
+        Reply::Status(503, "unavailable"),
+        Reply::Status(429, "slow down"),
+    ]);
+
+    let file = Fetcher::new(fast_config())
+        .unwrap()
+        .get(&server.url)
+        .unwrap();
+    assert_eq!(file.bytes, b"body { color: red; }");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn gives_up_after_the_configured_retries() {
+    let server = serve(vec![
+        Reply::Status(500, "error"),
+        Reply::Status(500, "error"),
+        Reply::Status(500, "error"),
+    ]);
+
+    let config = fast_config().with_retries(1);
+    let err = Fetcher::new(config).unwrap().get(&server.url).unwrap_err();
+    assert!(format!("{err:#}").contains("500"), "{err:#}");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn slow_servers_time_out() {
+    let server = serve(vec![Reply::Hang]);
+
+    let config = fast_config()
+        .with_timeout(Duration::from_millis(200))
+        .with_retries(0);
+    let start = Instant::now();
+    let result = Fetcher::new(config).unwrap().get(&server.url);
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(4));
+}
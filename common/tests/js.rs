@@ -0,0 +1,95 @@
+mod common;
+
+use common::TestDir;
+use manganis_common::{bundled_files, resolve_relative_import, ModuleGraph};
+
+#[test]
+fn bundled_files_follow_relative_imports() {
+    let dir = TestDir::with_files(
+        "graph",
+        &[
+            (
+                "main.js",
+                "import { a } from './a';\nimport * as b from \"./lib/b.mjs\";\nexport { c } from './lib';\nimport 'preact';\n// import './commented';\nconst text = `import './template'`;\nArray.from('./not-an-import');\n",
+            ),
+            ("a.js", "import { b } from './lib/b.mjs';\nexport const a = b;\n"),
+            ("lib/b.mjs", "import { a } from '../a.js';\nexport const b = 1;\n"),
+            ("lib/index.js", "export const c = 3;\n"),
+            ("commented.js", ""),
+            ("template.js", ""),
+            ("not-an-import.js", ""),
+        ],
+    );
+    assert_eq!(
+        bundled_files(&dir.join("main.js")),
+        [
+            dir.join("main.js"),
+            dir.join("a.js"),
+            dir.join("lib/b.mjs"),
+            dir.join("lib/index.js"),
+        ]
+    );
+}
+
+#[test]
+fn imports_resolve_like_the_bundler() {
+    let dir = TestDir::with_files(
+        "resolve",
+        &[("main.js", ""), ("utils.js", ""), ("widgets/index.mjs", "")],
+    );
+    let main = dir.join("main.js");
+    assert_eq!(
+        resolve_relative_import(&main, "./utils"),
+        Some(dir.join("utils.js"))
+    );
+    assert_eq!(
+        resolve_relative_import(&main, "./utils.js"),
+        Some(dir.join("utils.js"))
+    );
+    assert_eq!(
+        resolve_relative_import(&main, "./widgets"),
+        Some(dir.join("widgets/index.mjs"))
+    );
+    assert_eq!(resolve_relative_import(&main, "./missing"), None);
+    assert_eq!(resolve_relative_import(&main, "utils"), None);
+    // Missing imports are left for the bundler to report
+    assert_eq!(
+        bundled_files(&dir.join("missing.js")),
+        [dir.join("missing.js")]
+    );
+}
+
+#[test]
+fn the_graph_follows_dynamic_imports() {
+    let dir = TestDir::with_files(
+        "dynamic",
+        &[
+            (
+                "main.js",
+                "import { h } from 'preact';\nconst lazy = await import('./lazy.js');\nconst chart = await import ( \"./chart\" );\nimport('https://esm.sh/confetti');\nconsole.log(import.meta.url);\n",
+            ),
+            ("lazy.js", "export const lazy = 1;\n"),
+            ("chart.js", "import { missing } from './missing';\n"),
+        ],
+    );
+    let main = dir.join("main.js");
+    let graph = ModuleGraph::new(&main, |path| std::fs::read_to_string(path)).unwrap();
+    assert_eq!(
+        graph.files(),
+        [main.clone(), dir.join("lazy.js"), dir.join("chart.js")]
+    );
+    assert_eq!(graph.external_imports(), ["preact"]);
+    assert_eq!(
+        graph.dynamic_imports(),
+        [
+            (main.clone(), "./lazy.js".to_string()),
+            (main.clone(), "./chart".to_string()),
+        ]
+    );
+    assert_eq!(
+        graph.unresolved_imports(),
+        [(dir.join("chart.js"), "./missing".to_string())]
+    );
+    // The macro tracks the files that are loaded dynamically too
+    assert_eq!(bundled_files(&main), graph.files());
+}
@@ -0,0 +1,22 @@
+use manganis_common::{Lockfile, LOCKFILE_ENV, LOCKFILE_NAME};
+use std::path::Path;
+
+// Changing the environment is not thread safe, so everything that reads it runs in one test
+#[test]
+fn pins_assets_in_the_workspace_lockfile() {
+    std::env::remove_var(LOCKFILE_ENV);
+    let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let workspace_lockfile = workspace_root.join(LOCKFILE_NAME);
+    assert_eq!(Lockfile::path(), workspace_lockfile);
+
+    // Dependencies from a registry are compiled in the workspace root, and their sources are never written to
+    let registry = home::cargo_home()
+        .unwrap()
+        .join("registry/src/index.crates.io-6f17d22bba15001f/dependency-1.0.0");
+    std::env::set_var("CARGO_MANIFEST_DIR", &registry);
+    std::env::set_current_dir(workspace_root).unwrap();
+    assert_eq!(Lockfile::path(), workspace_lockfile);
+
+    std::env::set_var(LOCKFILE_ENV, "/pinned/manganis.lock");
+    assert_eq!(Lockfile::path(), Path::new("/pinned/manganis.lock"));
+}
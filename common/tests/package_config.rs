@@ -0,0 +1,110 @@
+mod common;
+
+use common::TestDir;
+use manganis_common::{CssOptions, FileOptions, ImageOptions, ImageType, JsOptions, PackageConfig};
+
+// Writes a Cargo.toml to a unique directory for each test
+fn manifest(name: &str, contents: &str) -> TestDir {
+    TestDir::with_files(name, &[("Cargo.toml", contents)])
+}
+
+#[test]
+fn reads_the_manganis_metadata() {
+    let dir = manifest(
+        "metadata",
+        r#"
+[package]
+name = "app"
+
+[package.metadata.manganis]
+image-format = "WebP"
+image-quality = 80
+minify-css = false
+base-dir = "assets"
+"#,
+    );
+    let config = PackageConfig::from_manifest(dir.join("Cargo.toml")).unwrap();
+    assert_eq!(config.image_format(), Some(ImageType::Webp));
+    assert_eq!(config.image_quality(), Some(80));
+    assert_eq!(config.minify_css(), Some(false));
+    assert_eq!(config.minify_js(), None);
+    assert_eq!(config.base_dir(), Some(std::path::Path::new("assets")));
+
+    let dir = manifest("missing", "[package]\nname = \"app\"\n");
+    assert_eq!(
+        PackageConfig::from_manifest(dir.join("Cargo.toml")).unwrap(),
+        PackageConfig::default()
+    );
+}
+
+#[test]
+fn ignores_unknown_keys() {
+    let dir = manifest(
+        "unknown",
+        r#"
+[package.metadata.manganis]
+minify-csss = false
+image-quality = 50
+"#,
+    );
+    let config = PackageConfig::from_manifest(dir.join("Cargo.toml")).unwrap();
+    assert_eq!(config.minify_css(), None);
+    assert_eq!(config.image_quality(), Some(50));
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let dir = manifest(
+        "invalid",
+        "[package.metadata.manganis]\nimage-quality = 101\n",
+    );
+    assert!(PackageConfig::from_manifest(dir.join("Cargo.toml")).is_err());
+
+    let dir = manifest(
+        "format",
+        "[package.metadata.manganis]\nimage-format = \"bmp\"\n",
+    );
+    assert!(PackageConfig::from_manifest(dir.join("Cargo.toml")).is_err());
+}
+
+#[test]
+fn options_of_the_macro_call_override_the_defaults() {
+    let dir = manifest(
+        "precedence",
+        r#"
+[package.metadata.manganis]
+image-format = "png"
+image-quality = 40
+minify-css = false
+minify-js = false
+"#,
+    );
+    let config = PackageConfig::from_manifest(dir.join("Cargo.toml")).unwrap();
+
+    let mut css = FileOptions::Css(CssOptions::default());
+    config.apply_defaults(&mut css);
+    assert_eq!(css, FileOptions::Css(CssOptions::new(false)));
+
+    // The macro applies the options of the call after the defaults
+    let mut js = FileOptions::Js(JsOptions::default());
+    config.apply_defaults(&mut js);
+    assert_eq!(js, FileOptions::Js(JsOptions::new(false)));
+    if let FileOptions::Js(js) = &mut js {
+        js.set_minify(true);
+    }
+    assert_eq!(js, FileOptions::Js(JsOptions::new(true)));
+
+    let mut image = config.image_options();
+    assert_eq!(image.ty(), &ImageType::Png);
+    assert_eq!(image.quality(), Some(40));
+    image.set_ty(ImageType::Avif);
+    image.set_quality(Some(90));
+    let mut expected = ImageOptions::new(ImageType::Avif, None);
+    expected.set_quality(Some(90));
+    assert_eq!(image, expected);
+
+    assert_eq!(
+        PackageConfig::default().image_options(),
+        ImageOptions::new(ImageType::Avif, None)
+    );
+}
@@ -0,0 +1,128 @@
+use manganis_common::{
+    section::{decode_section, encode_asset, find_records, SectionError, FORMAT_VERSION},
+    AssetType, MetadataAsset, TailwindAsset,
+};
+
+fn metadata(key: &str) -> AssetType {
+    AssetType::Metadata(MetadataAsset::new(key, "value"))
+}
+
+// Sets the format version of an encoded record
+fn with_version(mut record: Vec<u8>, version: u16) -> Vec<u8> {
+    record[4..6].copy_from_slice(&version.to_le_bytes());
+    record
+}
+
+#[test]
+fn round_trips_records_with_padding() {
+    let tailwind = AssetType::Tailwind(TailwindAsset::new("flex"));
+    let mut data = encode_asset("first", &metadata("title"));
+    // Linkers may align the records in the section with null bytes
+    data.extend_from_slice(&[0; 7]);
+    data.extend(encode_asset("second", &tailwind));
+    data.extend_from_slice(&[0; 3]);
+
+    let decoded = decode_section(&data);
+    assert_eq!(decoded.errors, []);
+    assert_eq!(decoded.records.len(), 2);
+
+    let first = &decoded.records[0];
+    assert_eq!(first.package(), "first");
+    assert_eq!(first.format_version(), FORMAT_VERSION);
+    assert_eq!(first.manganis_version(), env!("CARGO_PKG_VERSION"));
+    assert_eq!(first.asset(), &metadata("title"));
+    assert_eq!(decoded.records[1].package(), "second");
+    assert_eq!(decoded.records[1].clone().into_asset(), tailwind);
+}
+
+#[test]
+fn truncates_long_package_names() {
+    let package = "a".repeat(300);
+    let decoded = decode_section(&encode_asset(&package, &metadata("title")));
+    assert_eq!(decoded.errors, []);
+    assert_eq!(decoded.records[0].package(), &package[..255]);
+}
+
+#[test]
+fn reports_truncated_records() {
+    let mut data = encode_asset("first", &metadata("title"));
+    let second = encode_asset("second", &metadata("description"));
+    data.extend_from_slice(&second[..second.len() - 1]);
+
+    let decoded = decode_section(&data);
+    assert_eq!(decoded.records.len(), 1);
+    assert_eq!(decoded.errors, [SectionError::Truncated]);
+
+    // A section that ends inside the header is truncated too
+    let decoded = decode_section(&second[..6]);
+    assert_eq!(decoded.records, []);
+    assert_eq!(decoded.errors, [SectionError::Truncated]);
+}
+
+#[test]
+fn reports_unsupported_versions_and_keeps_decoding() {
+    let mut data = with_version(
+        encode_asset("future", &metadata("title")),
+        FORMAT_VERSION + 1,
+    );
+    data.extend(with_version(encode_asset("ancient", &metadata("title")), 0));
+    data.extend(encode_asset("current", &metadata("title")));
+
+    let decoded = decode_section(&data);
+    assert_eq!(decoded.records.len(), 1);
+    assert_eq!(decoded.records[0].package(), "current");
+    assert_eq!(
+        decoded.errors,
+        [
+            SectionError::UnsupportedVersion {
+                format_version: FORMAT_VERSION + 1,
+                package: "future".to_string(),
+                manganis_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            SectionError::UnsupportedVersion {
+                format_version: 0,
+                package: "ancient".to_string(),
+                manganis_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        ]
+    );
+    assert!(decoded.errors[0].to_string().contains("`future`"));
+}
+
+#[test]
+fn reports_invalid_payloads() {
+    let mut record = encode_asset("broken", &metadata("title"));
+    let last = record.len() - 1;
+    record[last] = b'!';
+
+    let decoded = decode_section(&record);
+    assert_eq!(decoded.records, []);
+    assert!(matches!(
+        &decoded.errors[..],
+        [SectionError::InvalidPayload { package, .. }] if package == "broken"
+    ));
+}
+
+#[test]
+fn reports_unframed_legacy_data() {
+    // Older versions of manganis wrote the JSON of each asset without a header
+    let mut data = serde_json::to_vec(&metadata("title")).unwrap();
+    data.extend(encode_asset("current", &metadata("title")));
+
+    let decoded = decode_section(&data);
+    assert_eq!(decoded.records.len(), 1);
+    assert_eq!(decoded.errors, [SectionError::Unframed]);
+}
+
+#[test]
+fn finds_records_between_unrelated_bytes() {
+    let first = encode_asset("first", &metadata("title"));
+    let second = encode_asset("second", &metadata("description"));
+    let mut data = b"unrelated MGAS data".to_vec();
+    data.extend(&first);
+    data.extend_from_slice(b"\x01\x02\x03");
+    data.extend(&second);
+    data.extend_from_slice(b"MGAS");
+
+    assert_eq!(find_records(&data), [&first[..], &second[..]]);
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::TestDir;
+use manganis_common::{
+    verified_remote_asset, Integrity, IntegrityAlgorithm, Lockfile, LOCKFILE_ENV, VENDOR_DIR_ENV,
+};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+};
+use url::Url;
+
+/// Serves the same body for every request
+fn serve(body: &'static str) -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!(
+        "http://{}/script.js",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    url
+}
+
+// The lockfile and vendor cache are set through the environment, so everything runs in one test
+#[test]
+fn a_download_that_does_not_match_its_integrity_is_never_pinned() {
+    let dir = TestDir::new("vendor");
+    let lockfile = dir.join("manganis.lock");
+    std::env::set_var(LOCKFILE_ENV, &lockfile);
+    std::env::set_var(VENDOR_DIR_ENV, dir.join("vendor"));
+
+    let body = "console.log('hello')";
+    let url = serve(body);
+
+    let wrong = Integrity::of(IntegrityAlgorithm::Sha256, b"something else");
+    let err = verified_remote_asset(&url, Some(&wrong)).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("does not match its integrity"),
+        "{err:#}"
+    );
+    assert!(Lockfile::load(&lockfile).unwrap().get(&url).is_none());
+    let vendored = std::fs::read_dir(dir.join("vendor"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name() != "lockfile.guard")
+                .count()
+        })
+        .unwrap_or(0);
+    assert_eq!(vendored, 0);
+
+    // The same URL is pinned once the content matches
+    let right = Integrity::of(IntegrityAlgorithm::Sha256, body.as_bytes());
+    let asset = verified_remote_asset(&url, Some(&right)).unwrap();
+    assert_eq!(Lockfile::load(&lockfile).unwrap().get(&url), Some(&asset));
+    assert_eq!(
+        std::fs::read_to_string(asset.vendored_path()).unwrap(),
+        body
+    );
+
+    // A pinned asset is still checked against the integrity of each call
+    assert!(verified_remote_asset(&url, Some(&wrong)).is_err());
+}
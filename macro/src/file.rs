@@ -2,35 +2,92 @@ use manganis_common::{AssetType, FileAsset};
 use quote::{quote, ToTokens};
 use syn::{parenthesized, parse::Parse};
 
-use crate::generate_link_section;
+use crate::{
+    generate_link_section, package_config, parse_integrity, parse_source, verify_integrity,
+};
 
 pub struct FileAssetParser {
     file_name: String,
+    embedded: Option<String>,
     asset: AssetType,
 }
 
+enum ParseFileOption {
+    Embed,
+    Integrity(manganis_common::Integrity),
+}
+
+impl Parse for ParseFileOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let _ = input.parse::<syn::Token![.]>()?;
+        let ident = input.parse::<syn::Ident>()?;
+        let content;
+        parenthesized!(content in input);
+        match ident.to_string().as_str() {
+            "embed" => Ok(ParseFileOption::Embed),
+            "integrity" => Ok(ParseFileOption::Integrity(parse_integrity(
+                &content.parse()?,
+            )?)),
+            _ => Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "Unknown file option: {}. Supported options are embed, integrity",
+                    ident
+                ),
+            )),
+        }
+    }
+}
+
 impl Parse for FileAssetParser {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let inside;
         parenthesized!(inside in input);
         let path = inside.parse::<syn::LitStr>()?;
 
-        let path_as_str = path.value();
-        let path = match path_as_str.parse() {
-            Ok(path) => path,
-            Err(e) => {
-                return Err(syn::Error::new(
-                    proc_macro2::Span::call_site(),
-                    format!("{e}"),
-                ))
+        let mut embed = false;
+        let mut integrity = None;
+        while !input.is_empty() {
+            match input.parse::<ParseFileOption>()? {
+                ParseFileOption::Embed => embed = true,
+                ParseFileOption::Integrity(hash) => integrity = Some(hash),
             }
-        };
-        let this_file = FileAsset::new(path);
+        }
+
+        let config = package_config()?;
+        let path = parse_source(&path.value(), &config, integrity.as_ref())?;
+        let mut this_file = FileAsset::new(path);
+
+        // Apply the defaults the crate set in its Cargo.toml
+        this_file.with_options_mut(|options| config.apply_defaults(options));
+        this_file.set_embedded(embed);
+        this_file.set_integrity(integrity);
+        verify_integrity(&this_file)?;
         let asset = manganis_common::AssetType::File(this_file.clone());
 
         let file_name = this_file.served_location();
 
-        Ok(FileAssetParser { file_name, asset })
+        let embedded = if embed {
+            #[cfg(not(feature = "embed"))]
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "Embedding is not enabled. Enable the embed feature to use this feature",
+            ));
+            #[cfg(feature = "embed")]
+            Some(
+                crate::process_asset(&this_file)?
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        } else {
+            None
+        };
+
+        Ok(FileAssetParser {
+            file_name,
+            embedded,
+            asset,
+        })
     }
 }
 
@@ -40,11 +97,20 @@ impl ToTokens for FileAssetParser {
 
         let link_section = generate_link_section(self.asset.clone());
 
-        tokens.extend(quote! {
-            {
-                #link_section
-                #file_name
-            }
-        })
+        match &self.embedded {
+            // include_bytes makes cargo rebuild the crate when the processed file changes
+            Some(processed) => tokens.extend(quote! {
+                {
+                    #link_section
+                    manganis::EmbeddedAsset::new(#file_name, include_bytes!(#processed))
+                }
+            }),
+            None => tokens.extend(quote! {
+                {
+                    #link_section
+                    #file_name
+                }
+            }),
+        }
     }
 }
@@ -0,0 +1,63 @@
+use manganis_common::{AssetType, FolderAsset};
+use quote::{quote, ToTokens};
+use syn::parenthesized;
+
+use crate::{generate_link_section, package_config};
+
+pub struct FolderAssetParser {
+    location: String,
+    files: Vec<(String, String)>,
+    asset: AssetType,
+}
+
+impl FolderAssetParser {
+    /// Parses the arguments of `dir("static/")` if `glob` is false or `glob("icons/*.svg")` if it is true
+    pub fn parse(input: syn::parse::ParseStream, glob: bool) -> syn::Result<Self> {
+        let inside;
+        parenthesized!(inside in input);
+        let path = inside.parse::<syn::LitStr>()?;
+
+        let config = package_config()?;
+        let folder = if glob {
+            FolderAsset::from_glob(&path.value(), config.asset_dir(), &config)
+        } else {
+            FolderAsset::from_dir(&path.value(), config.asset_dir(), &config)
+        }
+        .map_err(|e| syn::Error::new(path.span(), format!("{e}")))?;
+
+        let location = folder.served_location();
+        let files = folder
+            .files()
+            .iter()
+            .map(|file| {
+                (
+                    file.relative_path().to_string(),
+                    folder.served_file_location(file),
+                )
+            })
+            .collect();
+
+        Ok(FolderAssetParser {
+            location,
+            files,
+            asset: AssetType::Folder(folder),
+        })
+    }
+}
+
+impl ToTokens for FolderAssetParser {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let location = &self.location;
+        let relative_paths = self.files.iter().map(|(relative, _)| relative);
+        let served = self.files.iter().map(|(_, served)| served);
+
+        let link_section = generate_link_section(self.asset.clone());
+
+        tokens.extend(quote! {
+            {
+                #link_section
+                manganis::FolderAsset::new(#location, &[#((#relative_paths, #served)),*])
+            }
+        })
+    }
+}
@@ -1,8 +1,10 @@
-use manganis_common::{AssetType, FileAsset, FileOptions, FileSource, ImageOptions};
+use manganis_common::{AssetType, FileAsset, FileOptions};
 use quote::{quote, ToTokens};
 use syn::{parenthesized, parse::Parse, Token};
 
-use crate::generate_link_section;
+use crate::{
+    generate_link_section, package_config, parse_integrity, parse_source, verify_integrity,
+};
 
 struct ParseImageOptions {
     options: Vec<ParseImageOption>,
@@ -16,6 +18,16 @@ impl ParseImageOptions {
     }
 }
 
+impl ParseImageOptions {
+    /// Returns the integrity the image must match, if one was set
+    fn integrity(&self) -> Option<&manganis_common::Integrity> {
+        self.options.iter().rev().find_map(|option| match option {
+            ParseImageOption::Integrity(integrity) => Some(integrity),
+            _ => None,
+        })
+    }
+}
+
 impl Parse for ParseImageOptions {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut options = Vec::new();
@@ -30,8 +42,10 @@ enum ParseImageOption {
     Format(manganis_common::ImageType),
     Size((u32, u32)),
     Preload(bool),
+    Quality(u8),
     UrlEncoded(bool),
     Lqip(bool),
+    Integrity(manganis_common::Integrity),
 }
 
 impl ParseImageOption {
@@ -39,7 +53,8 @@ impl ParseImageOption {
         match self {
             ParseImageOption::Format(_)
             | ParseImageOption::Size(_)
-            | ParseImageOption::Preload(_) => file.with_options_mut(|options| {
+            | ParseImageOption::Preload(_)
+            | ParseImageOption::Quality(_) => file.with_options_mut(|options| {
                 if let FileOptions::Image(options) = options {
                     match self {
                         ParseImageOption::Format(format) => {
@@ -51,6 +66,9 @@ impl ParseImageOption {
                         ParseImageOption::Preload(preload) => {
                             options.set_preload(preload);
                         }
+                        ParseImageOption::Quality(quality) => {
+                            options.set_quality(Some(quality));
+                        }
                         _ => {}
                     }
                 }
@@ -61,6 +79,9 @@ impl ParseImageOption {
             ParseImageOption::Lqip(lqip) => {
                 *low_quality_preview = lqip;
             }
+            ParseImageOption::Integrity(integrity) => {
+                file.set_integrity(Some(integrity));
+            }
         }
     }
 }
@@ -83,16 +104,30 @@ impl Parse for ParseImageOption {
             "preload" => {
                 Ok(ParseImageOption::Preload(true))
             }
+            "quality" => {
+                let quality = content.parse::<syn::LitInt>()?;
+                let quality: u8 = quality.base10_parse()?;
+                if quality > 100 {
+                    return Err(syn::Error::new(
+                        proc_macro2::Span::call_site(),
+                        format!("Image quality must be between 0 and 100, found {quality}"),
+                    ));
+                }
+                Ok(ParseImageOption::Quality(quality))
+            }
             "url_encoded" => {
                 Ok(ParseImageOption::UrlEncoded(true))
             }
             "low_quality_preview" => {
                 Ok(ParseImageOption::Lqip(true))
             }
+            "integrity" => {
+                Ok(ParseImageOption::Integrity(parse_integrity(&content.parse()?)?))
+            }
             _ => Err(syn::Error::new(
                 proc_macro2::Span::call_site(),
                 format!(
-                    "Unknown image option: {}. Supported options are format, size, preload, url_encoded, low_quality_preview",
+                    "Unknown image option: {}. Supported options are format, size, preload, quality, url_encoded, low_quality_preview, integrity",
                     ident
                 ),
             )),
@@ -178,24 +213,22 @@ impl Parse for ImageAssetParser {
             }
         };
 
-        let path_as_str = path.value();
-        let path: FileSource = match path_as_str.parse() {
-            Ok(path) => path,
-            Err(e) => {
-                return Err(syn::Error::new(
-                    proc_macro2::Span::call_site(),
-                    format!("{e}"),
-                ))
-            }
-        };
-        let mut this_file =
-            FileAsset::new(path.clone()).with_options(manganis_common::FileOptions::Image(
-                ImageOptions::new(manganis_common::ImageType::Avif, None),
-            ));
+        // The crate may set a default format and quality in its Cargo.toml
+        let config = package_config()?;
+        let path = parse_source(
+            &path.value(),
+            &config,
+            parsed_options
+                .as_ref()
+                .and_then(|options| options.integrity()),
+        )?;
+        let mut this_file = FileAsset::new(path.clone())
+            .with_options(manganis_common::FileOptions::Image(config.image_options()));
         let mut low_quality_preview = false;
         if let Some(parsed_options) = parsed_options {
             parsed_options.apply_to_options(&mut this_file, &mut low_quality_preview);
         }
+        verify_integrity(&this_file)?;
 
         let asset = manganis_common::AssetType::File(this_file.clone());
 
@@ -236,12 +269,13 @@ impl Parse for ImageAssetParser {
                         (width, height)
                     })
                     .unwrap_or((32, 32));
-                let lqip = FileAsset::new(path).with_options(manganis_common::FileOptions::Image(
-                    ImageOptions::new(
+                let mut lqip = FileAsset::new(path).with_options(
+                    manganis_common::FileOptions::Image(manganis_common::ImageOptions::new(
                         manganis_common::ImageType::Avif,
                         Some(low_quality_preview_size),
-                    ),
-                ));
+                    )),
+                );
+                lqip.set_integrity(this_file.location().integrity().cloned());
 
                 Some(url_encoded_asset(&lqip).map_err(|e| {
                     syn::Error::new(
@@ -266,24 +300,7 @@ impl Parse for ImageAssetParser {
 fn url_encoded_asset(file_asset: &FileAsset) -> Result<String, syn::Error> {
     use base64::Engine;
 
-    let target_directory =
-        std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
-    let output_folder = std::path::Path::new(&target_directory)
-        .join("manganis")
-        .join("assets");
-    std::fs::create_dir_all(&output_folder).map_err(|e| {
-        syn::Error::new(
-            proc_macro2::Span::call_site(),
-            format!("Failed to create output folder: {}", e),
-        )
-    })?;
-    manganis_cli_support::process_file(file_asset, &output_folder).map_err(|e| {
-        syn::Error::new(
-            proc_macro2::Span::call_site(),
-            format!("Failed to process file: {}", e),
-        )
-    })?;
-    let file = output_folder.join(file_asset.location().unique_name());
+    let file = crate::process_asset(file_asset)?;
     let data = std::fs::read(file).map_err(|e| {
         syn::Error::new(
             proc_macro2::Span::call_site(),
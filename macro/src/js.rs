@@ -0,0 +1,96 @@
+use manganis_common::{AssetType, FileAsset, FileOptions, FileSource, JsOptions};
+use quote::{quote, ToTokens};
+use syn::{parenthesized, parse::Parse};
+
+use crate::{
+    generate_link_section, package_config, parse_integrity, parse_source, verify_integrity,
+};
+
+pub struct JsAssetParser {
+    file_name: String,
+    asset: AssetType,
+}
+
+enum ParseJsOption {
+    Minify(bool),
+    Bundle,
+    SourceMap,
+    Integrity(manganis_common::Integrity),
+}
+
+impl Parse for ParseJsOption {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let _ = input.parse::<syn::Token![.]>()?;
+        let ident = input.parse::<syn::Ident>()?;
+        let content;
+        parenthesized!(content in input);
+        match ident.to_string().as_str() {
+            "minify" => Ok(ParseJsOption::Minify(content.parse::<syn::LitBool>()?.value)),
+            "bundle" => Ok(ParseJsOption::Bundle),
+            "source_map" => Ok(ParseJsOption::SourceMap),
+            "integrity" => Ok(ParseJsOption::Integrity(parse_integrity(
+                &content.parse()?,
+            )?)),
+            _ => Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "Unknown js option: {}. Supported options are minify, bundle, source_map, integrity",
+                    ident
+                ),
+            )),
+        }
+    }
+}
+
+impl Parse for JsAssetParser {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let inside;
+        parenthesized!(inside in input);
+        let path = inside.parse::<syn::LitStr>()?;
+
+        let config = package_config()?;
+        let mut options = JsOptions::new(config.minify_js().unwrap_or(true));
+        let mut integrity = None;
+        while !input.is_empty() {
+            match input.parse::<ParseJsOption>()? {
+                ParseJsOption::Minify(minify) => options.set_minify(minify),
+                ParseJsOption::Bundle => options.set_bundle(true),
+                ParseJsOption::SourceMap => options.set_source_map(true),
+                ParseJsOption::Integrity(hash) => integrity = Some(hash),
+            }
+        }
+
+        let source = parse_source(&path.value(), &config, integrity.as_ref())?;
+        if options.bundle() && matches!(source, FileSource::Remote(_)) {
+            return Err(syn::Error::new(
+                path.span(),
+                "Only local javascript files can be bundled",
+            ));
+        }
+        let mut this_file = FileAsset::new(source).with_options(FileOptions::Js(options));
+        this_file.set_integrity(integrity);
+        verify_integrity(&this_file)?;
+
+        let file_name = this_file.served_location();
+
+        Ok(JsAssetParser {
+            file_name,
+            asset: AssetType::File(this_file),
+        })
+    }
+}
+
+impl ToTokens for JsAssetParser {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let file_name = &self.file_name;
+
+        let link_section = generate_link_section(self.asset.clone());
+
+        tokens.extend(quote! {
+            {
+                #link_section
+                #file_name
+            }
+        })
+    }
+}
@@ -2,10 +2,14 @@
 #![deny(missing_docs)]
 
 use file::FileAssetParser;
+use folder::FolderAssetParser;
 use font::FontAssetParser;
 use image::ImageAssetParser;
+use js::JsAssetParser;
 use manganis_common::cache::macro_log_file;
-use manganis_common::{MetadataAsset, TailwindAsset};
+use manganis_common::{
+    FileAsset, FileSource, Integrity, MetadataAsset, PackageConfig, TailwindAsset,
+};
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
 use proc_macro2::TokenStream as TokenStream2;
@@ -15,8 +19,10 @@ use std::sync::atomic::Ordering;
 use syn::{parse::Parse, parse_macro_input, LitStr};
 
 mod file;
+mod folder;
 mod font;
 mod image;
+mod js;
 
 static LOG_FILE_FRESH: AtomicBool = AtomicBool::new(false);
 
@@ -35,29 +41,148 @@ fn trace_to_file() {
     }
 }
 
+/// Reads the `[package.metadata.manganis]` config of the crate the macro is used in
+fn package_config() -> syn::Result<PackageConfig> {
+    PackageConfig::current()
+        .map_err(|e| syn::Error::new(proc_macro2::Span::call_site(), format!("{e:#}")))
+}
+
+/// Parses the path or URL of an asset. Relative paths are resolved from the base directory of the package config
+///
+/// Remote assets are vendored up front and checked against their integrity before they are pinned in the lockfile. A failed download only fails the build in offline mode or if the asset has an integrity; otherwise it is reported when the asset is collected.
+fn parse_source(
+    path: &str,
+    config: &PackageConfig,
+    integrity: Option<&Integrity>,
+) -> syn::Result<FileSource> {
+    let source = FileSource::parse_relative_to(path, config.asset_dir())
+        .map_err(|e| syn::Error::new(proc_macro2::Span::call_site(), format!("{e}")))?;
+    if let FileSource::Remote(url) = &source {
+        if let Err(e) = manganis_common::verified_remote_asset(url, integrity) {
+            if integrity.is_some() || manganis_common::offline() {
+                return Err(syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!("{e:#}"),
+                ));
+            }
+            tracing::warn!("Failed to vendor the remote asset {url}: {e:#}");
+        }
+    }
+    Ok(source)
+}
+
+/// Parses the `sha256-...` argument of an `.integrity(..)` option
+fn parse_integrity(integrity: &LitStr) -> syn::Result<Integrity> {
+    integrity
+        .value()
+        .parse()
+        .map_err(|e| syn::Error::new(integrity.span(), format!("{e}")))
+}
+
+/// Reads the asset once to check it against its integrity, if it has one
+fn verify_integrity(file_asset: &FileAsset) -> syn::Result<()> {
+    if file_asset.location().integrity().is_some() {
+        file_asset
+            .location()
+            .read_to_bytes()
+            .map_err(|e| syn::Error::new(proc_macro2::Span::call_site(), format!("{e:#}")))?;
+    }
+    Ok(())
+}
+
+/// Processes an asset into the target directory and returns the absolute path to the processed file
+#[cfg(any(feature = "url-encoding", feature = "embed"))]
+fn process_asset(file_asset: &manganis_common::FileAsset) -> syn::Result<std::path::PathBuf> {
+    let target_directory =
+        std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+    let output_folder = std::path::Path::new(&target_directory)
+        .join("manganis")
+        .join("assets");
+    std::fs::create_dir_all(&output_folder).map_err(|e| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("Failed to create output folder: {}", e),
+        )
+    })?;
+    manganis_cli_support::process_file(file_asset, &output_folder).map_err(|e| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("Failed to process file: {}", e),
+        )
+    })?;
+    let file = output_folder.join(file_asset.location().unique_name());
+    file.canonicalize().map_err(|e| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("Failed to read file {}: {}", file.display(), e),
+        )
+    })
+}
+
 /// this new approach will store the assets descriptions *inside the executable*.
 /// The trick is to use the `link_section` attribute.
-/// We force rust to store a json representation of the asset description
+/// We force rust to store a framed record of the asset description
 /// inside a particular region of the binary, with the label "manganis".
 /// After linking, the "manganis" sections of the different executables will be merged.
 fn generate_link_section(asset: manganis_common::AssetType) -> TokenStream2 {
     let position = proc_macro2::Span::call_site();
 
-    let asset_description = serde_json::to_string(&asset).unwrap();
+    let package = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+    let asset_description = manganis_common::section::encode_asset(&package, &asset);
 
-    let len = asset_description.as_bytes().len();
+    let len = asset_description.len();
 
-    let asset_bytes = syn::LitByteStr::new(asset_description.as_bytes(), position);
+    let asset_bytes = syn::LitByteStr::new(&asset_description, position);
 
     let section_name = syn::LitStr::new(
         manganis_common::linker::LinkSection::CURRENT.link_section,
         position,
     );
 
+    let tracked_files = track_local_files(&asset);
+
     quote! {
         #[link_section = #section_name]
         #[used]
         static ASSET: [u8; #len] = * #asset_bytes;
+
+        #tracked_files
+    }
+}
+
+/// Makes cargo rebuild the crate when a local file the asset was built from changes.
+///
+/// The macro reads the files at expansion time, but cargo only tracks the files the compiler reads. Referencing each file with `include_bytes!` adds it to the dep-info of the crate.
+/// The constants are never used, so the bytes are not included in the binary. `proc_macro::tracked_path` would avoid reading the files, but it is only available on nightly.
+///
+/// Assets collected in dev mode are linked from their source under a stable name, so they are not tracked unless their content is compiled into the binary.
+fn track_local_files(asset: &manganis_common::AssetType) -> TokenStream2 {
+    let mut paths: Vec<std::path::PathBuf> = match asset {
+        manganis_common::AssetType::File(file)
+            if file.dev() && !file.url_encoded() && !file.embedded() =>
+        {
+            Vec::new()
+        }
+        manganis_common::AssetType::Folder(folder) if folder.dev() => Vec::new(),
+        // Bundled javascript is also rebuilt when a file it imports changes
+        manganis_common::AssetType::File(file) => file.source_files(),
+        manganis_common::AssetType::Folder(folder) => folder
+            .files()
+            .iter()
+            .flat_map(|file| file.asset().source_files())
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !paths.is_empty() {
+        // The defaults in [package.metadata.manganis] change how the files are processed
+        if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+            paths.push(std::path::Path::new(&manifest_dir).join("Cargo.toml"));
+        }
+    }
+
+    let paths = paths.iter().filter_map(|path| path.to_str());
+    quote! {
+        #(const _: &[u8] = include_bytes!(#paths);)*
     }
 }
 
@@ -101,6 +226,10 @@ pub fn classes(input: TokenStream) -> TokenStream {
 /// ```rust
 /// const _: &str = manganis::mg!(file("https://rustacean.net/assets/rustacean-flat-happy.png"));
 /// ```
+/// With the `embed` feature, you can embed the processed file in the binary. The file is still collected
+/// ```rust, ignore
+/// const DATA: manganis::EmbeddedAsset = manganis::mg!(file("src/asset.txt").embed());
+/// ```
 ///
 /// # Images
 ///
@@ -121,6 +250,29 @@ pub fn classes(input: TokenStream) -> TokenStream {
 /// const _: manganis::ImageAsset = manganis::mg!(image("rustacean-flat-gesture.png").preload());
 /// ```
 ///
+/// # Javascript
+///
+/// The js builder collects a javascript file and minifies it
+/// ```rust, ignore
+/// const INTEROP: &str = manganis::mg!(js("src/interop.js"));
+/// ```
+/// Bundle the relative imports of an ES module into one file and write a source map next to it
+/// ```rust, ignore
+/// const APP: &str = manganis::mg!(js("src/app.mjs").bundle().source_map());
+/// ```
+///
+/// # Folders
+///
+/// You can collect every file in a directory, or every file that matches a glob pattern. The files keep their structure relative to the directory, and each file is processed with the default options for its extension
+/// ```rust, ignore
+/// const STATIC: manganis::FolderAsset = manganis::mg!(dir("static/"));
+/// const ICONS: manganis::FolderAsset = manganis::mg!(glob("icons/**/*.svg"));
+/// ```
+/// Look up where a file is served from by its path relative to the directory:
+/// ```rust, ignore
+/// let arrow: Option<&str> = ICONS.get("arrows/left.svg");
+/// ```
+///
 /// # Fonts
 ///
 /// You can use the font builder to collect fonts that will be included in the final binary from google fonts
@@ -167,6 +319,8 @@ enum AnyAssetParser {
     File(FileAssetParser),
     Image(ImageAssetParser),
     Font(FontAssetParser),
+    Js(JsAssetParser),
+    Folder(FolderAssetParser),
 }
 
 impl Parse for AnyAssetParser {
@@ -178,11 +332,14 @@ impl Parse for AnyAssetParser {
             "file" => Self::File(input.parse::<FileAssetParser>()?),
             "image" => Self::Image(input.parse::<ImageAssetParser>()?),
             "font" => Self::Font(input.parse::<FontAssetParser>()?),
+            "js" => Self::Js(input.parse::<JsAssetParser>()?),
+            "dir" => Self::Folder(FolderAssetParser::parse(input, false)?),
+            "glob" => Self::Folder(FolderAssetParser::parse(input, true)?),
             _ => {
                 return Err(syn::Error::new(
                     proc_macro2::Span::call_site(),
                     format!(
-                        "Unknown asset type: {as_string}. Supported types are file, image, font"
+                        "Unknown asset type: {as_string}. Supported types are file, image, font, js, dir, glob"
                     ),
                 ))
             }
@@ -202,6 +359,12 @@ impl ToTokens for AnyAssetParser {
             Self::Font(font) => {
                 font.to_tokens(tokens);
             }
+            Self::Js(js) => {
+                js.to_tokens(tokens);
+            }
+            Self::Folder(folder) => {
+                folder.to_tokens(tokens);
+            }
         }
     }
 }
@@ -245,3 +408,11 @@ pub fn meta(input: TokenStream) -> TokenStream {
     .into_token_stream()
     .into()
 }
+
+#[doc(hidden)]
+/// Expands to the location assets are served from in the config of the current build, like `/assets/`. Used by `manganis::asset_location` to find the name of an asset in its location
+#[proc_macro]
+pub fn assets_location_prefix(_input: TokenStream) -> TokenStream {
+    let prefix = manganis_common::Config::current().asset_location("");
+    quote!(#prefix).into_token_stream().into()
+}
@@ -3,6 +3,47 @@
 
 pub use manganis_macro::*;
 
+mod resolve;
+pub use resolve::*;
+
+use std::{borrow::Cow, path::PathBuf};
+
+/// The environment variable that overrides where assets are served from at runtime on native targets.
+///
+/// If this is set to `https://cdn.example.com/assets/`, an asset that was compiled as `/assets/logo123.png` is served from `https://cdn.example.com/assets/logo123.png`.
+pub const ASSETS_BASE_ENV: &str = "MANGANIS_ASSETS_BASE";
+
+/// The location assets are served from in the config this crate was built with, like `/assets/`
+pub(crate) const ASSETS_LOCATION_PREFIX: &str = manganis_macro::assets_location_prefix!();
+
+/// Returns the location of an asset collected by the [`mg!`] macro, taking the [`ASSETS_BASE_ENV`] environment variable into account.
+///
+/// On native targets, if the environment variable is set, the serve location the asset was compiled with is replaced by the value of the variable. The rest of the location is kept, so files in a folder asset keep their folder and relative path.
+/// This lets the same build be deployed behind different hosts. URL encoded assets, locations outside of the serve location and assets on the web are returned unchanged.
+pub fn asset_location(location: &'static str) -> Cow<'static, str> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        static BASE: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+        let base = BASE.get_or_init(|| std::env::var(ASSETS_BASE_ENV).ok());
+
+        if let Some(base) = base {
+            let name = location
+                .strip_prefix(ASSETS_LOCATION_PREFIX)
+                .filter(|_| !location.starts_with("data:"));
+            if let Some(name) = name {
+                let separator = if base.is_empty() || base.ends_with('/') {
+                    ""
+                } else {
+                    "/"
+                };
+                return Cow::Owned(format!("{base}{separator}{name}"));
+            }
+        }
+    }
+
+    Cow::Borrowed(location)
+}
+
 /// An image asset that is built by the [`mg!`] macro
 #[derive(Debug, PartialEq, PartialOrd, Clone, Hash)]
 pub struct ImageAsset {
@@ -29,6 +70,18 @@ impl ImageAsset {
         self.path
     }
 
+    /// Finds the image on the filesystem. See [`asset_path`]
+    ///
+    /// Use this on desktop and mobile where the application may not be started from the directory that contains the assets.
+    pub fn resolve(&self) -> Option<PathBuf> {
+        asset_path(self.path)
+    }
+
+    /// Returns the location the image is served from, taking the [`ASSETS_BASE_ENV`] environment variable into account. See [`asset_location`]
+    pub fn location(&self) -> Cow<'static, str> {
+        asset_location(self.path)
+    }
+
     /// Returns the preview of the image
     pub const fn preview(&self) -> Option<&'static str> {
         self.preview
@@ -67,7 +120,7 @@ impl std::fmt::Display for ImageAsset {
 #[cfg(feature = "dioxus")]
 impl dioxus_core::prelude::IntoAttributeValue for ImageAsset {
     fn into_value(self) -> dioxus_core::AttributeValue {
-        dioxus_core::AttributeValue::Text(self.path.to_string())
+        dioxus_core::AttributeValue::Text(self.location().into_owned())
     }
 }
 
@@ -146,6 +199,34 @@ impl ImageAssetBuilder {
         Self
     }
 
+    /// Sets the quality (0-100) the image is encoded with
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// A lower quality makes lossy formats like jpg and avif smaller. Webp images are always encoded losslessly
+    ///
+    /// ```rust
+    /// const _: manganis::ImageAsset = manganis::mg!(image("https://avatars.githubusercontent.com/u/79236386?s=48&v=4").quality(60));
+    /// ```
+    #[allow(unused)]
+    pub const fn quality(self, quality: u8) -> Self {
+        Self
+    }
+
+    /// Sets the hash the source image must match, like `sha256-<base64 digest>`
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// The image is checked when the macro downloads it and again when it is processed. If the content does not match, compilation fails
+    ///
+    /// ```rust, ignore
+    /// const _: manganis::ImageAsset = manganis::mg!(image("https://example.com/logo.png").integrity("sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="));
+    /// ```
+    #[allow(unused)]
+    pub const fn integrity(self, integrity: &'static str) -> Self {
+        Self
+    }
+
     /// Make the image URL encoded
     ///
     /// > **Note**: This will do nothing outside of the `mg!` macro
@@ -274,9 +355,242 @@ pub const fn font() -> FontAssetBuilder {
 /// ```rust
 /// const _: &str = manganis::mg!(file("https://rustacean.net/assets/rustacean-flat-happy.png"));
 /// ```
+/// Embed the processed file in the binary with the `embed` feature:
+/// ```rust, ignore
+/// const DATA: manganis::EmbeddedAsset = manganis::mg!(file("src/asset.txt").embed());
+/// ```
 #[allow(unused)]
-pub const fn file(path: &'static str) -> ImageAssetBuilder {
-    ImageAssetBuilder
+pub const fn file(path: &'static str) -> FileAssetBuilder {
+    FileAssetBuilder
+}
+
+/// A builder for a file asset. This must be used in the [`mg!`] macro.
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+pub struct FileAssetBuilder;
+
+impl FileAssetBuilder {
+    /// Embed the processed file in the binary. This requires the `embed` feature
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// The macro returns an [`EmbeddedAsset`] that contains the bytes of the file after manganis processed it. The file is still collected, so it can be served as well
+    ///
+    /// ```rust, ignore
+    /// const DATA: manganis::EmbeddedAsset = manganis::mg!(file("src/asset.txt").embed());
+    /// let bytes: &[u8] = DATA.bytes();
+    /// ```
+    #[allow(unused)]
+    pub const fn embed(self) -> Self {
+        Self
+    }
+
+    /// Sets the hash the file must match, like `sha256-<base64 digest>`
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// The file is checked when the macro downloads it and again when it is processed. If the content does not match, compilation fails
+    ///
+    /// ```rust, ignore
+    /// const _: &str = manganis::mg!(file("https://example.com/style.css").integrity("sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="));
+    /// ```
+    #[allow(unused)]
+    pub const fn integrity(self, integrity: &'static str) -> Self {
+        Self
+    }
+}
+
+/// Create a javascript asset from the local path or url to the file
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+///
+/// The js builder collects a javascript file and minifies it. `.js` and `.mjs` files collected with [`file`] are minified as well
+/// ```rust, ignore
+/// const INTEROP: &str = manganis::mg!(js("src/interop.js"));
+/// ```
+#[allow(unused)]
+pub const fn js(path: &'static str) -> JsAssetBuilder {
+    JsAssetBuilder
+}
+
+/// A builder for a javascript asset. This must be used in the [`mg!`] macro.
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+pub struct JsAssetBuilder;
+
+impl JsAssetBuilder {
+    /// Sets whether the javascript is minified. Defaults to true
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// ```rust, ignore
+    /// const _: &str = manganis::mg!(js("src/interop.js").minify(false));
+    /// ```
+    #[allow(unused)]
+    pub const fn minify(self, minify: bool) -> Self {
+        Self
+    }
+
+    /// Bundle the relative imports of the file into it
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// Static imports like `import { init } from "./init.js"` are bundled into a single ES module. Other imports, like packages and URLs, are kept. Only local files can be bundled.
+    ///
+    /// The name of the processed file changes when the file or any of its imports changes
+    ///
+    /// ```rust, ignore
+    /// const _: &str = manganis::mg!(js("src/app.mjs").bundle());
+    /// ```
+    #[allow(unused)]
+    pub const fn bundle(self) -> Self {
+        Self
+    }
+
+    /// Write a source map next to the processed file
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// The map is written to `<processed file>.map` and linked with a `sourceMappingURL` comment, so browser devtools show the original code
+    ///
+    /// ```rust, ignore
+    /// const _: &str = manganis::mg!(js("src/app.mjs").bundle().source_map());
+    /// ```
+    #[allow(unused)]
+    pub const fn source_map(self) -> Self {
+        Self
+    }
+
+    /// Sets the hash the source file must match, like `sha256-<base64 digest>`
+    ///
+    /// > **Note**: This will do nothing outside of the `mg!` macro
+    ///
+    /// ```rust, ignore
+    /// const _: &str = manganis::mg!(js("https://example.com/widget.js").integrity("sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="));
+    /// ```
+    #[allow(unused)]
+    pub const fn integrity(self, integrity: &'static str) -> Self {
+        Self
+    }
+}
+
+/// A file asset that is embedded in the binary, built by the [`mg!`] macro with [`FileAssetBuilder::embed`]
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Hash)]
+pub struct EmbeddedAsset {
+    /// The location the file is served from
+    path: &'static str,
+    /// The bytes of the processed file
+    bytes: &'static [u8],
+}
+
+impl EmbeddedAsset {
+    /// Creates a new embedded asset
+    pub const fn new(path: &'static str, bytes: &'static [u8]) -> Self {
+        Self { path, bytes }
+    }
+
+    /// Returns the location the file is served from
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Returns the bytes of the processed file
+    pub const fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+}
+
+impl std::ops::Deref for EmbeddedAsset {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.path
+    }
+}
+
+impl std::fmt::Display for EmbeddedAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+/// Collect every file in a directory and its subdirectories
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+///
+/// Relative paths are resolved relative to the package root. Each file is processed with the default options for its extension. Hidden files are skipped and symlinked directories are not followed.
+///
+/// Cargo rebuilds the crate when a collected file changes, but not when a new file is added to the directory. Touch a source file of the crate to pick up new files.
+/// ```rust, ignore
+/// const STATIC: manganis::FolderAsset = manganis::mg!(dir("static/"));
+/// ```
+#[allow(unused)]
+pub const fn dir(path: &'static str) -> FolderAssetBuilder {
+    FolderAssetBuilder
+}
+
+/// Collect every file that matches a glob pattern like `icons/**/*.svg`
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+///
+/// The files keep their structure relative to the part of the pattern before the first wildcard
+/// ```rust, ignore
+/// const ICONS: manganis::FolderAsset = manganis::mg!(glob("icons/**/*.svg"));
+/// ```
+#[allow(unused)]
+pub const fn glob(pattern: &'static str) -> FolderAssetBuilder {
+    FolderAssetBuilder
+}
+
+/// A builder for a folder asset. This must be used in the [`mg!`] macro.
+///
+/// > **Note**: This will do nothing outside of the `mg!` macro
+pub struct FolderAssetBuilder;
+
+/// A folder of files built by the [`mg!`] macro with [`dir`] or [`glob`]
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy, Hash)]
+pub struct FolderAsset {
+    /// The location the folder is served from
+    location: &'static str,
+    /// The path of each file relative to the folder and the location it is served from, sorted by path
+    files: &'static [(&'static str, &'static str)],
+}
+
+impl FolderAsset {
+    /// Creates a new folder asset
+    pub const fn new(
+        location: &'static str,
+        files: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        Self { location, files }
+    }
+
+    /// Returns the location the folder is served from
+    pub const fn location(&self) -> &'static str {
+        self.location
+    }
+
+    /// Returns the location a file is served from, by the path of the original file relative to the folder
+    ///
+    /// ```rust, ignore
+    /// const ICONS: manganis::FolderAsset = manganis::mg!(glob("icons/**/*.svg"));
+    /// let arrow = ICONS.get("arrows/left.svg").unwrap();
+    /// ```
+    pub fn get(&self, relative_path: &str) -> Option<&'static str> {
+        let relative_path = relative_path.replace('\\', "/");
+        let relative_path = relative_path
+            .trim_start_matches("./")
+            .trim_start_matches('/');
+        self.files
+            .binary_search_by(|(path, _)| (*path).cmp(relative_path))
+            .ok()
+            .map(|index| self.files[index].1)
+    }
+
+    /// Returns the path relative to the folder and the served location of every file
+    pub fn files(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.files.iter().copied()
+    }
 }
 
 /// A trait for something that can be used in the `mg!` macro
@@ -290,10 +604,16 @@ mod __private {
     pub trait Sealed {}
 
     impl Sealed for ImageAssetBuilder {}
+    impl Sealed for FileAssetBuilder {}
     impl Sealed for FontAssetBuilder {}
+    impl Sealed for JsAssetBuilder {}
+    impl Sealed for FolderAssetBuilder {}
     impl Sealed for &'static str {}
 }
 
 impl ForMgMacro for ImageAssetBuilder {}
+impl ForMgMacro for FileAssetBuilder {}
 impl ForMgMacro for FontAssetBuilder {}
+impl ForMgMacro for JsAssetBuilder {}
+impl ForMgMacro for FolderAssetBuilder {}
 impl ForMgMacro for &'static str {}
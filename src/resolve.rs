@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use crate::ASSETS_LOCATION_PREFIX;
+
+/// The environment variable that sets the directory assets are read from at runtime on native targets
+pub const ASSETS_DIR_ENV: &str = "MANGANIS_ASSETS_DIR";
+
+/// Finds the file of an asset collected by the [`crate::mg!`] macro on the filesystem.
+///
+/// The location an asset is compiled with is usually relative, like `./assets/logo123.png`, which only works if the application is started from the directory that contains the assets.
+/// This looks for the asset in these directories and returns the first file that exists:
+/// - The directory in the [`ASSETS_DIR_ENV`] environment variable. This is the directory the assets were collected into, so files in a folder asset are looked up under their folder
+/// - The directory of the executable
+/// - The `Resources` directory of a macOS app bundle
+/// - The `share/<executable name>` directory of a Linux install prefix
+/// - The current working directory
+///
+/// Returns `None` on the web, for URL encoded assets and if the asset cannot be found.
+pub fn asset_path(location: &str) -> Option<PathBuf> {
+    if cfg!(target_arch = "wasm32") || location.starts_with("data:") {
+        return None;
+    }
+
+    let relative = relative_location(location);
+
+    if let Some(dir) = std::env::var_os(ASSETS_DIR_ENV) {
+        // The directory contains the assets themselves, so the serve location is not part of the path
+        let name = location
+            .strip_prefix(ASSETS_LOCATION_PREFIX)
+            .map(|name| name.trim_start_matches('/'))
+            .unwrap_or(relative);
+        let path = Path::new(&dir).join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    if let Some(exe) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.canonicalize().ok())
+    {
+        if let Some(exe_dir) = exe.parent() {
+            candidates.push(exe_dir.to_path_buf());
+            if cfg!(target_os = "macos") {
+                candidates.push(exe_dir.join("../Resources"));
+            }
+            if cfg!(target_os = "linux") {
+                if let Some(name) = exe.file_name() {
+                    candidates.push(exe_dir.join("../share").join(name));
+                }
+            }
+        }
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        candidates.push(cwd);
+    }
+
+    candidates
+        .into_iter()
+        .map(|dir| dir.join(relative))
+        .find(|path| path.is_file())
+}
+
+/// Returns the location as a path relative to the directory that contains the assets
+fn relative_location(location: &str) -> &str {
+    // Drop the scheme and host of absolute URLs
+    let path = match location.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|i| &rest[i..]).unwrap_or(""),
+        None => location,
+    };
+    path.trim_start_matches("./").trim_start_matches('/')
+}
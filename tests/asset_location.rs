@@ -0,0 +1,32 @@
+use manganis::{asset_location, FolderAsset, ASSETS_BASE_ENV};
+
+const SOURCE: &str = manganis::mg!(file("src/lib.rs"));
+const FIXTURES: FolderAsset = manganis::mg!(dir("tests/fixtures"));
+
+// The base is read once, so every case runs in one test
+#[test]
+fn replaces_only_the_serve_location() {
+    std::env::set_var(ASSETS_BASE_ENV, "https://cdn.example.com/v3");
+
+    let location = asset_location(SOURCE);
+    let name = SOURCE.rsplit('/').next().unwrap();
+    assert_eq!(location, format!("https://cdn.example.com/v3/{name}"));
+
+    // Files in a folder keep the folder and their path in it
+    let nested = FIXTURES.get("nested/page.txt").unwrap();
+    let folder = FIXTURES.location().rsplit('/').next().unwrap();
+    assert_eq!(
+        asset_location(nested),
+        format!("https://cdn.example.com/v3/{folder}/nested/page.txt")
+    );
+
+    // Locations that were not collected by the macro are kept
+    assert_eq!(
+        asset_location("https://example.com/logo.png"),
+        "https://example.com/logo.png"
+    );
+    assert_eq!(
+        asset_location("data:image/png;base64,AAAA"),
+        "data:image/png;base64,AAAA"
+    );
+}
@@ -0,0 +1,31 @@
+use manganis::{asset_path, FolderAsset, ASSETS_DIR_ENV};
+
+const SOURCE: &str = manganis::mg!(file("src/lib.rs"));
+const FIXTURES: FolderAsset = manganis::mg!(dir("tests/fixtures"));
+
+// The directory is set through the environment, so every case runs in one test
+#[test]
+fn finds_assets_in_the_assets_dir() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("asset-path-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::env::set_var(ASSETS_DIR_ENV, &dir);
+
+    let name = SOURCE.rsplit('/').next().unwrap();
+    let folder = FIXTURES.location().rsplit('/').next().unwrap();
+    let nested = dir.join(folder).join("nested/page.txt");
+    std::fs::create_dir_all(nested.parent().unwrap()).unwrap();
+    std::fs::write(dir.join(name), "").unwrap();
+    std::fs::write(&nested, "").unwrap();
+    // A file with the same name at the top of the directory is not a match for the nested file
+    std::fs::write(dir.join("page.txt"), "").unwrap();
+
+    assert_eq!(asset_path(SOURCE), Some(dir.join(name)));
+    assert_eq!(
+        asset_path(FIXTURES.get("nested/page.txt").unwrap()),
+        Some(nested)
+    );
+    assert_eq!(asset_path("data:image/png;base64,AAAA"), None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}